@@ -0,0 +1,83 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use std::path::PathBuf;
+
+/// Show information read directly from the Linux kernel
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+  /// What to show; the kernel version and boot details by default
+  #[command(subcommand)]
+  pub command: Option<Commands>,
+
+  /// Where procfs is mounted; point it at a copied or fake tree to read that instead
+  #[arg(long, env = "PROC_ROOT", default_value = "/proc", global = true)]
+  pub proc_root: PathBuf,
+
+  /// Where sysfs is mounted
+  #[arg(long, env = "SYS_ROOT", default_value = "/sys", global = true)]
+  pub sys_root: PathBuf,
+
+  /// How to print the results
+  #[arg(short, long, value_enum, default_value = "table", global = true)]
+  pub format: Format,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  Table,
+  Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+  /// The logical CPUs with their model, clock, topology and caches
+  Cpu,
+  /// Memory and swap use from /proc/meminfo
+  Mem,
+  /// Loaded kernel modules, their dependencies and what taints the kernel
+  Modules {
+    /// Show the dependencies as a tree
+    #[arg(short, long)]
+    tree: bool,
+    /// Start the tree from the modules that need no others
+    #[arg(short, long, requires = "tree")]
+    reverse: bool,
+    /// Add the module parameters to the table
+    #[arg(short, long)]
+    parameters: bool,
+  },
+  /// Browse, compare and change the kernel parameters in /proc/sys
+  Sysctl {
+    #[command(subcommand)]
+    command: SysctlCommand,
+  },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SysctlCommand {
+  /// Parameters with their current values
+  List {
+    /// Only the keys matching this regular expression
+    pattern: Option<Regex>,
+    /// Print "key = value" lines, to save as a baseline for `diff`
+    #[arg(short, long)]
+    conf: bool,
+  },
+  /// Compare the current values with a sysctl.conf style file, or with the files in --conf-dir
+  Diff {
+    /// A baseline file; the files in --conf-dir are used without one
+    file: Option<PathBuf>,
+    #[arg(long, default_value = "/etc/sysctl.d")]
+    conf_dir: PathBuf,
+  },
+  /// Change parameters
+  Set {
+    /// New values as key=value
+    #[arg(required = true)]
+    assignments: Vec<String>,
+    /// Only show what would change
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+  },
+}
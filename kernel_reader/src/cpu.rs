@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// One logical CPU from /proc/cpuinfo, with its place in the machine from
+/// /sys/devices/system/cpu/cpu<N>
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Cpu {
+  pub processor: u32,
+  pub vendor: Option<String>,
+  pub model: Option<String>,
+  /// The current clock; cpuinfo has it on x86, cpufreq elsewhere
+  pub mhz: Option<f64>,
+  /// The "cache size" cpuinfo reports, usually the last level
+  pub cache_size: Option<String>,
+  /// Instruction set extensions and features ("flags" on x86, "Features" on ARM)
+  pub flags: Vec<String>,
+  pub topology: Topology,
+  pub caches: Vec<Cache>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Topology {
+  /// The socket
+  pub package: Option<u32>,
+  pub core: Option<u32>,
+  /// The logical CPUs sharing this core, like "0,4" or "0-1"
+  pub siblings: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Cache {
+  pub level: u32,
+  /// Data, Instruction or Unified
+  pub kind: String,
+  /// As sysfs writes it, like "48K"
+  pub size: String,
+  /// The logical CPUs using this cache
+  pub shared_with: String,
+}
+
+impl Cache {
+  /// The usual short name: L1d, L1i, L2, L3
+  pub fn name(&self) -> String {
+    match self.kind.as_str() {
+      "Data" => format!("L{}d", self.level),
+      "Instruction" => format!("L{}i", self.level),
+      _ => format!("L{}", self.level),
+    }
+  }
+}
+
+/// Parse /proc/cpuinfo: a block of "key : value" lines per logical CPU.
+/// Blocks without a processor number, like the "Hardware" block of older ARM
+/// kernels, are skipped.
+pub fn parse_cpuinfo(content: &str) -> Vec<Cpu> {
+  content
+    .split("\n\n")
+    .filter_map(|block| {
+      let field = |names: &[&str]| {
+        block.lines().find_map(|line| {
+          let (key, value) = line.split_once(':')?;
+          names
+            .contains(&key.trim())
+            .then(|| value.trim().to_string())
+        })
+      };
+
+      Some(Cpu {
+        processor: field(&["processor"])?.parse().ok()?,
+        vendor: field(&["vendor_id", "CPU implementer"]),
+        model: field(&["model name", "Processor", "cpu model", "cpu"]),
+        mhz: field(&["cpu MHz"]).and_then(|mhz| mhz.parse().ok()),
+        cache_size: field(&["cache size"]),
+        flags: field(&["flags", "Features"])
+          .map(|flags| flags.split_whitespace().map(String::from).collect())
+          .unwrap_or_default(),
+        ..Cpu::default()
+      })
+    })
+    .collect()
+}
+
+/// Every logical CPU, with topology and caches filled in from sysfs where
+/// it has them
+pub fn read_cpus(proc_root: &Path, sys_root: &Path) -> io::Result<Vec<Cpu>> {
+  let mut cpus = parse_cpuinfo(&fs::read_to_string(proc_root.join("cpuinfo"))?);
+  for cpu in &mut cpus {
+    let path = sys_root
+      .join("devices/system/cpu")
+      .join(format!("cpu{}", cpu.processor));
+    let read = |file: &str| {
+      fs::read_to_string(path.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+    };
+
+    cpu.topology = Topology {
+      package: read("topology/physical_package_id").and_then(|id| id.parse().ok()),
+      core: read("topology/core_id").and_then(|id| id.parse().ok()),
+      siblings: read("topology/thread_siblings_list"),
+    };
+    if cpu.mhz.is_none() {
+      cpu.mhz = read("cpufreq/scaling_cur_freq")
+        .and_then(|khz| khz.parse::<f64>().ok())
+        .map(|khz| khz / 1000.0);
+    }
+
+    let mut index = 0;
+    while let Some(level) = read(&format!("cache/index{}/level", index)) {
+      let entry = |file: &str| read(&format!("cache/index{}/{}", index, file)).unwrap_or_default();
+      cpu.caches.push(Cache {
+        level: level.parse().unwrap_or(0),
+        kind: entry("type"),
+        size: entry("size"),
+        shared_with: entry("shared_cpu_list"),
+      });
+      index += 1;
+    }
+  }
+  Ok(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn x86_and_arm_blocks() {
+    let x86 = parse_cpuinfo(
+      "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Xeon(R) Processor\n\
+       cpu MHz\t\t: 2100.000\ncache size\t: 307200 KB\nflags\t\t: fpu vme sse2\n\n\
+       processor\t: 1\nvendor_id\t: GenuineIntel\n",
+    );
+    assert_eq!(x86.len(), 2);
+    assert_eq!(x86[0].model.as_deref(), Some("Intel(R) Xeon(R) Processor"));
+    assert_eq!(x86[0].mhz, Some(2100.0));
+    assert_eq!(x86[0].cache_size.as_deref(), Some("307200 KB"));
+    assert_eq!(x86[0].flags, ["fpu", "vme", "sse2"]);
+    assert_eq!(x86[1].processor, 1);
+    assert_eq!(x86[1].mhz, None);
+
+    let arm = parse_cpuinfo(
+      "processor\t: 0\nBogoMIPS\t: 108.00\nFeatures\t: fp asimd evtstrm\n\
+       CPU implementer\t: 0x41\n\nHardware\t: BCM2835\nRevision\t: d03114\n",
+    );
+    assert_eq!(arm.len(), 1);
+    assert_eq!(arm[0].vendor.as_deref(), Some("0x41"));
+    assert_eq!(arm[0].flags, ["fp", "asimd", "evtstrm"]);
+  }
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::ffi::CStr;
+use std::fs;
+use std::io;
+use std::mem::MaybeUninit;
+use std::path::Path;
+
+use crate::version::KernelVersion;
+
+/// The system names from uname(2). These always describe the running kernel,
+/// whatever proc root is read.
+#[derive(Debug, Serialize)]
+pub struct Uname {
+  pub sysname: String,
+  pub nodename: String,
+  pub release: String,
+  pub version: String,
+  pub machine: String,
+  pub domainname: String,
+}
+
+impl Uname {
+  pub fn read() -> io::Result<Uname> {
+    let mut names = MaybeUninit::<libc::utsname>::uninit();
+    // SAFETY: uname fills the whole struct when it succeeds
+    let names = unsafe {
+      if libc::uname(names.as_mut_ptr()) != 0 {
+        return Err(io::Error::last_os_error());
+      }
+      names.assume_init()
+    };
+    let text = |field: &[libc::c_char]| {
+      // SAFETY: the kernel NUL-terminates every field within its array
+      unsafe { CStr::from_ptr(field.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+    };
+
+    Ok(Uname {
+      sysname: text(&names.sysname),
+      nodename: text(&names.nodename),
+      release: text(&names.release),
+      version: text(&names.version),
+      machine: text(&names.machine),
+      domainname: text(&names.domainname),
+    })
+  }
+}
+
+/// What the kernel says about itself
+#[derive(Debug, Serialize)]
+pub struct KernelInfo {
+  /// /proc/version as it is
+  pub banner: String,
+  /// None if the banner is not in the usual Linux format
+  pub version: Option<KernelVersion>,
+  pub uname: Uname,
+  /// The parameters the kernel was booted with, from /proc/cmdline
+  pub cmdline: Option<String>,
+  /// Seconds since the epoch, from the btime line of /proc/stat
+  pub boot_time: Option<u64>,
+}
+
+impl KernelInfo {
+  /// Fails only if /proc/version cannot be read
+  pub fn read(proc_root: &Path) -> io::Result<KernelInfo> {
+    let banner = fs::read_to_string(proc_root.join("version"))?
+      .trim_end()
+      .to_string();
+    let cmdline = fs::read_to_string(proc_root.join("cmdline"))
+      .ok()
+      .map(|cmdline| cmdline.trim_end().to_string());
+    let boot_time = fs::read_to_string(proc_root.join("stat"))
+      .ok()
+      .and_then(|stat| {
+        stat
+          .lines()
+          .find_map(|line| line.strip_prefix("btime "))
+          .and_then(|value| value.trim().parse().ok())
+      });
+
+    Ok(KernelInfo {
+      version: KernelVersion::parse(&banner),
+      banner,
+      uname: Uname::read()?,
+      cmdline,
+      boot_time,
+    })
+  }
+}
@@ -1,10 +1,478 @@
+use chrono::{DateTime, Local};
+use clap::Parser;
+use prettytable::{Cell, Row, Table, row};
+use serde::Serialize;
 use std::fs;
 use std::io;
+use std::path::Path;
+
+mod cli;
+mod cpu;
+mod info;
+mod mem;
+mod modules;
+mod sysctl;
+mod version;
+
+use cli::{Cli, Commands, Format, SysctlCommand};
+use cpu::Cpu;
+use info::KernelInfo;
+use mem::{MemInfo, human};
+use modules::Module;
 
 fn main() -> io::Result<()> {
-  println!("🔍 Reading information directly from the Linux kernel...");
-  let kernel_version = fs::read_to_string("/proc/version")?;
-  println!("\n✅ The kernel version running on the system:");
-  println!("{}", kernel_version);
+  let cli = Cli::parse();
+
+  match cli.command {
+    None => {
+      let info = KernelInfo::read(&cli.proc_root)?;
+      match cli.format {
+        Format::Json => print_json(&info),
+        Format::Table => print_info(&info),
+      }
+    }
+    Some(Commands::Cpu) => {
+      let cpus = cpu::read_cpus(&cli.proc_root, &cli.sys_root)?;
+      match cli.format {
+        Format::Json => print_json(&cpus),
+        Format::Table => print_cpus(&cpus),
+      }
+    }
+    Some(Commands::Mem) => {
+      let mem = MemInfo::read(&cli.proc_root)?;
+      match cli.format {
+        Format::Json => print_json(&mem),
+        Format::Table => print_mem(&mem),
+      }
+    }
+    Some(Commands::Modules {
+      tree,
+      reverse,
+      parameters,
+    }) => {
+      // Without module support there is no /proc/modules, but the kernel can still be tainted
+      let modules = match modules::read_modules(&cli.proc_root, &cli.sys_root) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        result => Some(result?),
+      };
+      let tainted = modules::read_tainted(&cli.proc_root);
+      match (cli.format, &modules) {
+        (Format::Json, _) => print_json(&ModulesReport {
+          tainted,
+          taint_flags: tainted.map_or(Vec::new(), |mask| {
+            modules::decode_taint(mask)
+              .iter()
+              .map(|flag| flag.letter)
+              .collect()
+          }),
+          unknown_taint_bits: tainted.map_or(Vec::new(), modules::unknown_taint_bits),
+          modules: modules.as_deref().unwrap_or_default(),
+        }),
+        (Format::Table, None) => {
+          println!("This kernel was built without loadable module support");
+          print_taint(tainted);
+        }
+        (Format::Table, Some(modules)) if tree => {
+          println!("{}", modules::render_tree(modules, reverse))
+        }
+        (Format::Table, Some(modules)) => {
+          print_modules(modules, parameters);
+          print_taint(tainted);
+        }
+      }
+    }
+    Some(Commands::Sysctl { command }) => run_sysctl(&cli.proc_root, command, cli.format)?,
+  }
+  Ok(())
+}
+
+fn print_json(value: &impl Serialize) {
+  println!(
+    "{}",
+    serde_json::to_string_pretty(value).expect("the values are plain data")
+  );
+}
+
+fn print_info(info: &KernelInfo) {
+  let mut table = Table::new();
+  table.add_row(row![bFg->"Field", bFb->"Value"]);
+  match &info.version {
+    Some(version) => {
+      table.add_row(row!["Release", version.release]);
+      table.add_row(row!["Build host", version.build_host]);
+      table.add_row(row!["Compiler", version.compiler]);
+      if let Some(linker) = &version.linker {
+        table.add_row(row!["Linker", linker]);
+      }
+      table.add_row(row!["Build", version.build]);
+      table.add_row(row!["Build date", version.build_date]);
+    }
+    None => {
+      table.add_row(row!["Version", info.banner]);
+    }
+  }
+
+  let uname = &info.uname;
+  table.add_row(row![
+    "System",
+    format!("{} {}", uname.sysname, uname.machine)
+  ]);
+  table.add_row(row!["Host name", uname.nodename]);
+  table.add_row(row!["Domain name", uname.domainname]);
+  table.add_row(row![
+    "Running kernel",
+    format!("{} {}", uname.release, uname.version)
+  ]);
+
+  if let Some(cmdline) = &info.cmdline {
+    table.add_row(row!["Command line", wrap(cmdline, 80)]);
+  }
+  if let Some(boot_time) = info.boot_time
+    && let Some(time) = DateTime::from_timestamp(boot_time as i64, 0)
+  {
+    let time = time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %Z");
+    table.add_row(row!["Boot time", time]);
+  }
+  table.printstd();
+}
+
+fn print_cpus(cpus: &[Cpu]) {
+  let mut table = Table::new();
+  table.add_row(row![
+    bFg->"CPU", bFb->"Model", b->"MHz", b->"Package", b->"Core", b->"Threads", b->"Caches"
+  ]);
+  for cpu in cpus {
+    let optional = |value: Option<u32>| value.map_or(String::new(), |value| value.to_string());
+    let caches: Vec<String> = cpu
+      .caches
+      .iter()
+      .map(|cache| format!("{} {}", cache.name(), cache.size))
+      .collect();
+    table.add_row(row![
+      cpu.processor,
+      cpu.model.as_deref().unwrap_or("?"),
+      cpu.mhz.map_or(String::new(), |mhz| format!("{:.0}", mhz)),
+      optional(cpu.topology.package),
+      optional(cpu.topology.core),
+      cpu.topology.siblings.as_deref().unwrap_or(""),
+      caches.join(", ")
+    ]);
+  }
+  table.printstd();
+
+  let mut cores: Vec<(Option<u32>, Option<u32>)> = cpus
+    .iter()
+    .map(|cpu| (cpu.topology.package, cpu.topology.core))
+    .collect();
+  cores.sort();
+  cores.dedup();
+  let mut packages: Vec<Option<u32>> = cores.iter().map(|(package, _)| *package).collect();
+  packages.dedup();
+  println!(
+    "{} logical CPUs, {} cores, {} packages",
+    cpus.len(),
+    cores.len(),
+    packages.len()
+  );
+
+  // Flags are usually the same everywhere; only differences are listed per CPU
+  let Some(first) = cpus.first() else {
+    return;
+  };
+  let common: Vec<&str> = first
+    .flags
+    .iter()
+    .filter(|flag| cpus.iter().all(|cpu| cpu.flags.contains(flag)))
+    .map(String::as_str)
+    .collect();
+  println!("\nFlags:\n{}", wrap(&common.join(" "), 100));
+  for cpu in cpus {
+    let extra: Vec<&str> = cpu
+      .flags
+      .iter()
+      .map(String::as_str)
+      .filter(|flag| !common.contains(flag))
+      .collect();
+    if !extra.is_empty() {
+      println!("CPU {} also: {}", cpu.processor, extra.join(" "));
+    }
+  }
+}
+
+fn print_mem(mem: &MemInfo) {
+  let percent = |part: u64| {
+    if mem.total == 0 {
+      String::new()
+    } else {
+      format!("{:.1}%", part as f64 * 100.0 / mem.total as f64)
+    }
+  };
+
+  let mut table = Table::new();
+  table.add_row(row![bFg->"Memory", bFb->"Size", b->"Of total"]);
+  for (name, value) in [
+    ("Total", mem.total),
+    ("Used", mem.used),
+    ("Free", mem.free),
+    ("Available", mem.available),
+    ("Buffers", mem.buffers),
+    ("Cached", mem.cached),
+    ("Shared", mem.shmem),
+    ("Slab", mem.slab),
+    ("  reclaimable", mem.slab_reclaimable),
+    ("Active", mem.active),
+    ("Inactive", mem.inactive),
+    ("Anonymous", mem.anon_pages),
+    ("Mapped", mem.mapped),
+    ("Page tables", mem.page_tables),
+    ("Dirty", mem.dirty),
+    ("Writeback", mem.writeback),
+    ("Committed", mem.committed),
+    ("Commit limit", mem.commit_limit),
+  ] {
+    table.add_row(row![name, r->human(value), r->percent(value)]);
+  }
+  if mem.huge_pages_total > 0 {
+    table.add_row(row![
+      "Huge pages",
+      r->format!(
+        "{} of {} free",
+        mem.huge_pages_free, mem.huge_pages_total
+      ),
+      r->human(mem.huge_pages_total * mem.huge_page_size)
+    ]);
+  }
+  table.printstd();
+
+  if mem.swap_total == 0 {
+    println!("No swap");
+  } else {
+    println!(
+      "Swap: {} of {} used ({:.1}%)",
+      human(mem.swap_used),
+      human(mem.swap_total),
+      mem.swap_used_percent
+    );
+  }
+}
+
+#[derive(Serialize)]
+struct ModulesReport<'a> {
+  /// The mask from /proc/sys/kernel/tainted
+  tainted: Option<u64>,
+  /// Its letters, as in the kernel's "Tainted:" lines
+  taint_flags: Vec<char>,
+  /// Set bits without a known meaning
+  unknown_taint_bits: Vec<u32>,
+  modules: &'a [Module],
+}
+
+fn print_modules(modules: &[Module], show_parameters: bool) {
+  let mut table = Table::new();
+  let mut titles = row![
+    bFg->"Module", b->"Size", b->"Refs", bFb->"Used by", b->"State", bFr->"Taint"
+  ];
+  if show_parameters {
+    titles.add_cell(Cell::new("Parameters").style_spec("b"));
+  }
+  table.add_row(titles);
+
+  for module in modules {
+    let taint: Vec<&str> = module
+      .taint
+      .chars()
+      .map(modules::describe_module_taint)
+      .collect();
+    let mut cells = vec![
+      (module.name.clone(), ""),
+      (human(module.size / 1024), "r"),
+      (
+        module
+          .refcount
+          .map_or("-".to_string(), |count| count.to_string()),
+        "r",
+      ),
+      (module.used_by.join(", "), ""),
+      (module.state.clone(), ""),
+      (taint.join(", "), ""),
+    ];
+    if show_parameters {
+      let parameters: Vec<String> = module
+        .parameters
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+      cells.push((parameters.join("\n"), ""));
+    }
+    // Out-of-tree and other tainting modules stand out in red
+    let tainting = !module.taint.is_empty();
+    let row = Row::new(
+      cells
+        .iter()
+        .map(|(text, align)| {
+          let spec = if tainting {
+            format!("{}Fr", align)
+          } else {
+            align.to_string()
+          };
+          Cell::new(text).style_spec(&spec)
+        })
+        .collect(),
+    );
+    table.add_row(row);
+  }
+  table.printstd();
+}
+
+fn print_taint(tainted: Option<u64>) {
+  match tainted {
+    None => {}
+    Some(0) => println!("The kernel is not tainted"),
+    Some(mask) => {
+      let flags = modules::decode_taint(mask);
+      let letters: String = flags.iter().map(|flag| flag.letter).collect();
+      println!("The kernel is tainted: {} ({})", mask, letters);
+      for flag in flags {
+        println!("  {} (bit {}): {}", flag.letter, flag.bit, flag.description);
+      }
+      for bit in modules::unknown_taint_bits(mask) {
+        println!("  unknown bit {}", bit);
+      }
+    }
+  }
+}
+
+fn run_sysctl(proc_root: &Path, command: SysctlCommand, format: Format) -> io::Result<()> {
+  match command {
+    SysctlCommand::List { pattern, conf } => {
+      let mut parameters = sysctl::read_all(proc_root);
+      if let Some(pattern) = pattern {
+        parameters.retain(|parameter| pattern.is_match(&parameter.key));
+      }
+      if format == Format::Json {
+        print_json(&parameters);
+      } else if conf {
+        for parameter in &parameters {
+          println!("{} = {}", parameter.key, parameter.value);
+        }
+      } else {
+        let mut table = Table::new();
+        table.add_row(row![bFg->"Key", bFb->"Value"]);
+        for parameter in &parameters {
+          table.add_row(row![parameter.key, parameter.value]);
+        }
+        table.printstd();
+      }
+    }
+
+    SysctlCommand::Diff { file, conf_dir } => {
+      let expected = match &file {
+        Some(file) => sysctl::parse_conf(&fs::read_to_string(file)?),
+        None => sysctl::read_conf_dir(&conf_dir)?,
+      };
+      let differences = sysctl::diff(proc_root, &expected);
+      if format == Format::Json {
+        print_json(&differences);
+        return Ok(());
+      }
+      if differences.is_empty() {
+        println!("All {} settings match", expected.len());
+        return Ok(());
+      }
+      let mut table = Table::new();
+      table.add_row(row![bFg->"Key", bFb->"Expected", bFr->"Current"]);
+      for difference in &differences {
+        table.add_row(row![
+          difference.key,
+          difference.expected,
+          difference.current.as_deref().unwrap_or("(missing)")
+        ]);
+      }
+      table.printstd();
+      println!(
+        "{} of {} settings differ",
+        differences.len(),
+        expected.len()
+      );
+    }
+
+    SysctlCommand::Set {
+      assignments,
+      dry_run,
+    } => {
+      // Check every assignment before writing any
+      let mut changes = Vec::new();
+      for assignment in &assignments {
+        let Some((key, value)) = assignment.split_once('=') else {
+          return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Expected key=value, got '{}'", assignment),
+          ));
+        };
+        let key = sysctl::dotted(key.trim());
+        let value = sysctl::normalize(value);
+        if value.is_empty() {
+          return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: expected a value", key),
+          ));
+        }
+        let current = sysctl::read(proc_root, &key)
+          .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", key, e)))?;
+        changes.push((key, current, value));
+      }
+
+      let mut table = Table::new();
+      table.add_row(row![bFg->"Key", bFr->"Current", bFb->"New"]);
+      for (key, current, value) in &changes {
+        table.add_row(row![key, current, value]);
+      }
+      table.printstd();
+      if dry_run {
+        println!("Dry run, nothing was written");
+        return Ok(());
+      }
+      // The kernel can still refuse a value, so the ones already written
+      // are put back when a later write fails
+      let mut written: Vec<(&String, &String)> = Vec::new();
+      for (key, current, value) in &changes {
+        if current == value {
+          continue;
+        }
+        if let Err(e) = sysctl::write(proc_root, key, value) {
+          for (key, current) in written.iter().rev() {
+            match sysctl::write(proc_root, key, current) {
+              Ok(()) => eprintln!("Restored {} = {}", key, current),
+              Err(e) => eprintln!("Could not restore {} = {}: {}", key, current, e),
+            }
+          }
+          let hint = if e.kind() == io::ErrorKind::PermissionDenied {
+            " (changing kernel parameters needs root)"
+          } else {
+            ""
+          };
+          return Err(io::Error::new(e.kind(), format!("{}: {}{}", key, e, hint)));
+        }
+        println!("{} = {}", key, value);
+        written.push((key, current));
+      }
+    }
+  }
   Ok(())
 }
+
+// Break text into lines of at most `width` characters at spaces, so long
+// values keep the table readable
+fn wrap(text: &str, width: usize) -> String {
+  let mut lines: Vec<String> = Vec::new();
+  for word in text.split_whitespace() {
+    match lines.last_mut() {
+      Some(line) if line.len() + 1 + word.len() <= width => {
+        line.push(' ');
+        line.push_str(word);
+      }
+      _ => lines.push(word.to_string()),
+    }
+  }
+  lines.join("\n")
+}
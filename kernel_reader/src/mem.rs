@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The main values of /proc/meminfo in KiB, plus figures derived from them.
+/// Values the kernel does not report are 0.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct MemInfo {
+  pub total: u64,
+  pub free: u64,
+  /// An estimate of what can be allocated without swapping, since Linux 3.14
+  pub available: u64,
+  pub buffers: u64,
+  /// The page cache, without the swap cache
+  pub cached: u64,
+  pub swap_cached: u64,
+  pub active: u64,
+  pub inactive: u64,
+  pub shmem: u64,
+  pub slab: u64,
+  pub slab_reclaimable: u64,
+  pub anon_pages: u64,
+  pub mapped: u64,
+  pub page_tables: u64,
+  pub dirty: u64,
+  pub writeback: u64,
+  pub swap_total: u64,
+  pub swap_free: u64,
+  /// How much may be committed under strict overcommit, and how much is
+  pub commit_limit: u64,
+  pub committed: u64,
+  pub huge_pages_total: u64,
+  pub huge_pages_free: u64,
+  pub huge_page_size: u64,
+
+  /// total - available
+  pub used: u64,
+  pub available_percent: f64,
+  pub swap_used: u64,
+  pub swap_used_percent: f64,
+}
+
+impl MemInfo {
+  pub fn parse(content: &str) -> MemInfo {
+    let value = |key: &str| {
+      content
+        .lines()
+        .find_map(|line| {
+          let (name, value) = line.split_once(':')?;
+          (name == key).then_some(value)
+        })
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0)
+    };
+
+    let total = value("MemTotal");
+    let free = value("MemFree");
+    let buffers = value("Buffers");
+    let cached = value("Cached");
+    // Older kernels have no estimate; free memory and caches come close
+    let available = match value("MemAvailable") {
+      0 => free + buffers + cached,
+      available => available,
+    };
+    let swap_total = value("SwapTotal");
+    let swap_free = value("SwapFree");
+    let percent = |part: u64, whole: u64| {
+      if whole == 0 {
+        0.0
+      } else {
+        part as f64 * 100.0 / whole as f64
+      }
+    };
+
+    MemInfo {
+      total,
+      free,
+      available,
+      buffers,
+      cached,
+      swap_cached: value("SwapCached"),
+      active: value("Active"),
+      inactive: value("Inactive"),
+      shmem: value("Shmem"),
+      slab: value("Slab"),
+      slab_reclaimable: value("SReclaimable"),
+      anon_pages: value("AnonPages"),
+      mapped: value("Mapped"),
+      page_tables: value("PageTables"),
+      dirty: value("Dirty"),
+      writeback: value("Writeback"),
+      swap_total,
+      swap_free,
+      commit_limit: value("CommitLimit"),
+      committed: value("Committed_AS"),
+      huge_pages_total: value("HugePages_Total"),
+      huge_pages_free: value("HugePages_Free"),
+      huge_page_size: value("Hugepagesize"),
+      used: total.saturating_sub(available),
+      available_percent: percent(available, total),
+      swap_used: swap_total.saturating_sub(swap_free),
+      swap_used_percent: percent(swap_total.saturating_sub(swap_free), swap_total),
+    }
+  }
+
+  pub fn read(proc_root: &Path) -> io::Result<MemInfo> {
+    Ok(MemInfo::parse(&fs::read_to_string(
+      proc_root.join("meminfo"),
+    )?))
+  }
+}
+
+/// A size in KiB in the largest unit that keeps it at least 1, like "5.3 GiB"
+pub fn human(kib: u64) -> String {
+  let units = ["KiB", "MiB", "GiB", "TiB"];
+  let mut value = kib as f64;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < units.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{} KiB", kib)
+  } else {
+    format!("{:.1} {}", value, units[unit])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn derived_values() {
+    let info = MemInfo::parse(
+      "MemTotal:        8000000 kB\nMemFree:         1000000 kB\nMemAvailable:    6000000 kB\n\
+       Cached:          4000000 kB\nActive(anon):         20 kB\nActive:          1437060 kB\n\
+       SwapTotal:       2000000 kB\nSwapFree:        1500000 kB\nHugePages_Total:       4\n",
+    );
+    assert_eq!(info.total, 8000000);
+    assert_eq!(info.active, 1437060);
+    assert_eq!(info.huge_pages_total, 4);
+    assert_eq!(info.used, 2000000);
+    assert_eq!(info.available_percent, 75.0);
+    assert_eq!(info.swap_used, 500000);
+    assert_eq!(info.swap_used_percent, 25.0);
+
+    // Before MemAvailable existed, and without swap
+    let old =
+      MemInfo::parse("MemTotal: 1000 kB\nMemFree: 100 kB\nBuffers: 50 kB\nCached: 250 kB\n");
+    assert_eq!(old.available, 400);
+    assert_eq!(old.swap_used_percent, 0.0);
+  }
+
+  #[test]
+  fn human_units() {
+    assert_eq!(human(512), "512 KiB");
+    assert_eq!(human(2048), "2.0 MiB");
+    assert_eq!(human(6158152), "5.9 GiB");
+  }
+}
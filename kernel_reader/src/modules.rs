@@ -0,0 +1,311 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A loaded module from /proc/modules, with details from /sys/module/<name>
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Module {
+  pub name: String,
+  /// Memory used by the module, in bytes
+  pub size: u64,
+  /// None when the kernel cannot unload modules
+  pub refcount: Option<u32>,
+  /// The modules that depend on this one
+  pub used_by: Vec<String>,
+  /// Live, Loading or Unloading
+  pub state: String,
+  /// Taint letters like "OE"; empty for a clean in-tree module
+  pub taint: String,
+  /// Version of out-of-tree modules that declare one
+  pub version: Option<String>,
+  /// The readable parameters and their values
+  pub parameters: BTreeMap<String, String>,
+}
+
+/// A bit of /proc/sys/kernel/tainted
+pub struct TaintFlag {
+  pub bit: u32,
+  pub letter: char,
+  pub description: &'static str,
+}
+
+const fn flag(bit: u32, letter: char, description: &'static str) -> TaintFlag {
+  TaintFlag {
+    bit,
+    letter,
+    description,
+  }
+}
+
+/// The taint bits as Documentation/admin-guide/tainted-kernels.rst lists them
+pub const TAINT_FLAGS: [TaintFlag; 20] = [
+  flag(0, 'P', "proprietary module was loaded"),
+  flag(1, 'F', "module was force loaded"),
+  flag(2, 'S', "kernel running on an out of specification system"),
+  flag(3, 'R', "module was force unloaded"),
+  flag(4, 'M', "processor reported a Machine Check Exception"),
+  flag(5, 'B', "bad page referenced or some unexpected page flags"),
+  flag(6, 'U', "taint requested by userspace application"),
+  flag(
+    7,
+    'D',
+    "kernel died recently, i.e. there was an OOPS or BUG",
+  ),
+  flag(8, 'A', "ACPI table overridden by user"),
+  flag(9, 'W', "kernel issued warning"),
+  flag(10, 'C', "staging driver was loaded"),
+  flag(11, 'I', "workaround for bug in platform firmware applied"),
+  flag(
+    12,
+    'O',
+    "externally-built (\"out-of-tree\") module was loaded",
+  ),
+  flag(13, 'E', "unsigned module was loaded"),
+  flag(14, 'L', "soft lockup occurred"),
+  flag(15, 'K', "kernel has been live patched"),
+  flag(16, 'X', "auxiliary taint, defined for and used by distros"),
+  flag(
+    17,
+    'T',
+    "kernel was built with the struct randomization plugin",
+  ),
+  flag(18, 'N', "an in-kernel test has been run"),
+  flag(
+    19,
+    'J',
+    "userspace used a mutating debug operation in fwctl",
+  ),
+];
+
+/// The flags set in a tainted mask, in bit order
+pub fn decode_taint(mask: u64) -> Vec<&'static TaintFlag> {
+  TAINT_FLAGS
+    .iter()
+    .filter(|flag| mask & (1 << flag.bit) != 0)
+    .collect()
+}
+
+/// The set bits of a tainted mask that newer kernels define but TAINT_FLAGS
+/// does not know about
+pub fn unknown_taint_bits(mask: u64) -> Vec<u32> {
+  (0..64)
+    .filter(|&bit| mask & (1 << bit) != 0)
+    .filter(|&bit| !TAINT_FLAGS.iter().any(|flag| flag.bit == bit))
+    .collect()
+}
+
+/// What a module's own taint letter means
+pub fn describe_module_taint(letter: char) -> &'static str {
+  match letter {
+    'P' => "proprietary",
+    'O' => "out-of-tree",
+    'E' => "unsigned",
+    'C' => "staging",
+    'F' => "forced",
+    'K' => "live patch",
+    'X' => "auxiliary",
+    'T' => "randstruct",
+    'N' => "test",
+    _ => "unknown",
+  }
+}
+
+/// Parse /proc/modules, where every line is
+/// "name size refcount used,by, state address (taint)"
+pub fn parse_modules(content: &str) -> Vec<Module> {
+  content
+    .lines()
+    .filter_map(|line| {
+      let fields: Vec<&str> = line.split_whitespace().collect();
+      let [name, size, refcount, used_by, state, ..] = fields[..] else {
+        return None;
+      };
+      // After the address come the taint letters, then '+' or '-' while
+      // the module is loading or unloading
+      let taint = fields
+        .iter()
+        .skip(6)
+        .find_map(|field| field.strip_prefix('(')?.strip_suffix(')'))
+        .unwrap_or("")
+        .trim_end_matches(['+', '-'])
+        .to_string();
+
+      Some(Module {
+        name: name.to_string(),
+        size: size.parse().ok()?,
+        refcount: refcount.parse().ok(),
+        used_by: used_by
+          .split(',')
+          .filter(|user| !user.is_empty() && *user != "-")
+          .map(String::from)
+          .collect(),
+        state: state.to_string(),
+        taint,
+        ..Module::default()
+      })
+    })
+    .collect()
+}
+
+/// The loaded modules with their parameters, sorted by name
+pub fn read_modules(proc_root: &Path, sys_root: &Path) -> io::Result<Vec<Module>> {
+  let mut modules = parse_modules(&fs::read_to_string(proc_root.join("modules"))?);
+  for module in &mut modules {
+    let path = sys_root.join("module").join(&module.name);
+    module.version = fs::read_to_string(path.join("version"))
+      .ok()
+      .map(|version| version.trim().to_string());
+
+    // Some parameters are write-only; those are left out
+    let Ok(entries) = fs::read_dir(path.join("parameters")) else {
+      continue;
+    };
+    for entry in entries.flatten() {
+      if let Ok(value) = fs::read_to_string(entry.path()) {
+        module.parameters.insert(
+          entry.file_name().to_string_lossy().into_owned(),
+          value.trim_end().to_string(),
+        );
+      }
+    }
+  }
+  modules.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(modules)
+}
+
+/// The taint mask of the running kernel; 0 means untainted
+pub fn read_tainted(proc_root: &Path) -> Option<u64> {
+  fs::read_to_string(proc_root.join("sys/kernel/tainted"))
+    .ok()?
+    .trim()
+    .parse()
+    .ok()
+}
+
+/// Draw the dependency graph as a tree. By default the modules nothing
+/// depends on are the roots, under each the modules it needs; `reverse`
+/// starts from the modules that need nothing and shows what uses them.
+/// A module shared by several others appears under each of them.
+pub fn render_tree(modules: &[Module], reverse: bool) -> String {
+  let children = |module: &Module| -> Vec<&Module> {
+    if reverse {
+      modules
+        .iter()
+        .filter(|other| module.used_by.contains(&other.name))
+        .collect()
+    } else {
+      modules
+        .iter()
+        .filter(|other| other.used_by.contains(&module.name))
+        .collect()
+    }
+  };
+  let roots = modules.iter().filter(|module| {
+    if reverse {
+      !modules
+        .iter()
+        .any(|other| other.used_by.contains(&module.name))
+    } else {
+      // Users that are not loaded modules, like a refcount from userspace, do not count
+      !module
+        .used_by
+        .iter()
+        .any(|user| modules.iter().any(|other| &other.name == user))
+    }
+  });
+
+  let mut lines = Vec::new();
+  for root in roots {
+    lines.push(root.name.clone());
+    draw(root, "", &children, &mut vec![&root.name], &mut lines);
+  }
+  lines.join("\n")
+}
+
+fn draw<'a>(
+  module: &'a Module,
+  prefix: &str,
+  children: &dyn Fn(&'a Module) -> Vec<&'a Module>,
+  path: &mut Vec<&'a str>,
+  lines: &mut Vec<String>,
+) {
+  let below = children(module);
+  for (index, child) in below.iter().enumerate() {
+    let last = index == below.len() - 1;
+    lines.push(format!(
+      "{}{}{}",
+      prefix,
+      if last { "└─ " } else { "├─ " },
+      child.name
+    ));
+    // A cycle cannot happen in a real kernel, but a hand-written file could have one
+    if path.contains(&child.name.as_str()) {
+      continue;
+    }
+    path.push(&child.name);
+    let prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+    draw(child, &prefix, children, path, lines);
+    path.pop();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MODULES: &str = "\
+nvidia_drm 90112 4 - Live 0xffffffffc19e0000 (POE)
+nvidia 56823808 312 nvidia_drm, Live 0xffffffffbfa00000 (POE)
+snd_hda_intel 61440 3 - Live 0x0000000000000000
+snd 135168 8 snd_hda_intel, Live 0x0000000000000000
+soundcore 16384 1 snd, Live 0x0000000000000000
+wireguard 118784 0 - Loading 0xffffffffc0b00000 (OE+)
+";
+
+  #[test]
+  fn parses_lines() {
+    let modules = parse_modules(MODULES);
+    assert_eq!(modules.len(), 6);
+    assert_eq!(
+      modules[1],
+      Module {
+        name: "nvidia".to_string(),
+        size: 56823808,
+        refcount: Some(312),
+        used_by: vec!["nvidia_drm".to_string()],
+        state: "Live".to_string(),
+        taint: "POE".to_string(),
+        ..Module::default()
+      }
+    );
+    assert!(modules[0].used_by.is_empty());
+    assert_eq!(modules[2].taint, "");
+    assert_eq!(modules[5].state, "Loading");
+    assert_eq!(modules[5].taint, "OE");
+  }
+
+  #[test]
+  fn taint_bits() {
+    let letters: String = decode_taint(12289).iter().map(|flag| flag.letter).collect();
+    assert_eq!(letters, "POE");
+    assert!(decode_taint(0).is_empty());
+    assert!(unknown_taint_bits(12289).is_empty());
+    assert_eq!(unknown_taint_bits(1 | 1 << 20 | 1 << 63), [20, 63]);
+    assert_eq!(describe_module_taint('O'), "out-of-tree");
+  }
+
+  #[test]
+  fn dependency_trees() {
+    let modules = parse_modules(MODULES);
+    assert_eq!(
+      render_tree(&modules, false),
+      "nvidia_drm\n└─ nvidia\nsnd_hda_intel\n└─ snd\n   └─ soundcore\nwireguard"
+    );
+    assert_eq!(
+      render_tree(&modules, true),
+      "nvidia\n└─ nvidia_drm\nsoundcore\n└─ snd\n   └─ snd_hda_intel\nwireguard"
+    );
+  }
+}
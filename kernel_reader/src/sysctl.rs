@@ -0,0 +1,291 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A kernel parameter from /proc/sys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Parameter {
+  /// Dotted like sysctl(8): "net.ipv4.ip_forward"
+  pub key: String,
+  /// With runs of whitespace, like the tabs in kernel.printk, made single spaces
+  pub value: String,
+  /// Set with a leading '-' in a conf file: a missing parameter is not a difference
+  #[serde(skip)]
+  pub optional: bool,
+}
+
+/// A setting whose current value is not the expected one
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Difference {
+  pub key: String,
+  pub expected: String,
+  /// None if the parameter does not exist or cannot be read
+  pub current: Option<String>,
+}
+
+// Dots inside a file name, as in "net/ipv4/conf/eth0.1", are written as
+// slashes in a key so the two can be told apart, as sysctl(8) does
+fn path_to_key(relative: &Path) -> String {
+  relative
+    .iter()
+    .map(|component| component.to_string_lossy().replace('.', "/"))
+    .collect::<Vec<_>>()
+    .join(".")
+}
+
+// Slashes in a key become dots in a name, so "kernel.//" would otherwise
+// climb out of /proc/sys
+fn key_to_path(proc_root: &Path, key: &str) -> io::Result<PathBuf> {
+  let mut path = proc_root.join("sys");
+  for component in key.split('.') {
+    let name = component.replace('/', ".");
+    if name.is_empty() || name == "." || name == ".." {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid parameter name {}", key),
+      ));
+    }
+    path.push(name);
+  }
+  Ok(path)
+}
+
+pub fn normalize(value: &str) -> String {
+  value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The current value of one parameter
+pub fn read(proc_root: &Path, key: &str) -> io::Result<String> {
+  let path = key_to_path(proc_root, key)?;
+  Ok(normalize(&fs::read_to_string(path)?))
+}
+
+/// Every readable parameter, sorted by key. Write-only parameters and those
+/// needing more privileges are left out.
+pub fn read_all(proc_root: &Path) -> Vec<Parameter> {
+  let base = proc_root.join("sys");
+  let mut parameters = Vec::new();
+  let mut directories = vec![base.clone()];
+  while let Some(directory) = directories.pop() {
+    let Ok(entries) = fs::read_dir(&directory) else {
+      continue;
+    };
+    for entry in entries.flatten() {
+      let path = entry.path();
+      if entry.file_type().is_ok_and(|t| t.is_dir()) {
+        directories.push(path);
+      } else if let Ok(value) = fs::read_to_string(&path)
+        && let Ok(relative) = path.strip_prefix(&base)
+      {
+        parameters.push(Parameter {
+          key: path_to_key(relative),
+          value: normalize(&value),
+          optional: false,
+        });
+      }
+    }
+  }
+  parameters.sort_by(|a, b| a.key.cmp(&b.key));
+  parameters
+}
+
+// Keys may also be written with slashes, "net/ipv4/conf/eth0.1/forwarding",
+// when the first separator is one; the dots are then part of the names
+pub fn dotted(key: &str) -> String {
+  match (key.find('/'), key.find('.')) {
+    (Some(slash), Some(dot)) if slash > dot => key.to_string(),
+    (Some(_), _) => key
+      .chars()
+      .map(|c| match c {
+        '/' => '.',
+        '.' => '/',
+        c => c,
+      })
+      .collect(),
+    (None, _) => key.to_string(),
+  }
+}
+
+/// Settings from a sysctl.conf(5) file: "key = value" lines, with # or ;
+/// comments. A leading '-' tells sysctl to ignore the setting if the
+/// parameter does not exist.
+pub fn parse_conf(content: &str) -> Vec<Parameter> {
+  content
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+    .filter_map(|line| {
+      let (key, value) = line.split_once('=')?;
+      let key = key.trim();
+      let optional = key.starts_with('-');
+      Some(Parameter {
+        key: dotted(key.strip_prefix('-').unwrap_or(key)),
+        value: normalize(value),
+        optional,
+      })
+    })
+    .collect()
+}
+
+/// The settings of every *.conf file in a directory like /etc/sysctl.d,
+/// read in name order so later files override earlier ones
+pub fn read_conf_dir(directory: &Path) -> io::Result<Vec<Parameter>> {
+  let mut files: Vec<PathBuf> = fs::read_dir(directory)?
+    .flatten()
+    .map(|entry| entry.path())
+    .filter(|path| {
+      path
+        .extension()
+        .is_some_and(|extension| extension == "conf")
+    })
+    .collect();
+  files.sort();
+
+  let mut settings: Vec<Parameter> = Vec::new();
+  for file in files {
+    for setting in parse_conf(&fs::read_to_string(file)?) {
+      settings.retain(|earlier| earlier.key != setting.key);
+      settings.push(setting);
+    }
+  }
+  Ok(settings)
+}
+
+/// The expected settings whose current value differs. Optional settings for
+/// parameters this kernel does not have are left out.
+pub fn diff(proc_root: &Path, expected: &[Parameter]) -> Vec<Difference> {
+  expected
+    .iter()
+    .filter_map(|setting| {
+      let current = read(proc_root, &setting.key).ok();
+      if current.is_none() && setting.optional {
+        return None;
+      }
+      (current.as_deref() != Some(setting.value.as_str())).then(|| Difference {
+        key: setting.key.clone(),
+        expected: setting.value.clone(),
+        current,
+      })
+    })
+    .collect()
+}
+
+/// Write a new value, which needs root for nearly every parameter
+pub fn write(proc_root: &Path, key: &str, value: &str) -> io::Result<()> {
+  let path = key_to_path(proc_root, key)?;
+  // Never create files in a copied tree
+  if !path.is_file() {
+    return Err(io::Error::new(
+      io::ErrorKind::NotFound,
+      format!("Unknown parameter {}", key),
+    ));
+  }
+  fs::write(path, format!("{}\n", value))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc")
+  }
+
+  #[test]
+  fn keys_and_paths() {
+    let key = path_to_key(Path::new("net/ipv4/conf/eth0.1/forwarding"));
+    assert_eq!(key, "net.ipv4.conf.eth0/1.forwarding");
+    assert_eq!(
+      key_to_path(Path::new("/proc"), &key).unwrap(),
+      Path::new("/proc/sys/net/ipv4/conf/eth0.1/forwarding")
+    );
+    assert_eq!(dotted("net/ipv4/conf/eth0.1/forwarding"), key);
+    assert_eq!(dotted(&key), key);
+    assert_eq!(dotted("vm.swappiness"), "vm.swappiness");
+  }
+
+  #[test]
+  fn keys_stay_under_proc_sys() {
+    for key in [
+      "kernel.//.//.//.etc.passwd",
+      "kernel./",
+      "kernel..hostname",
+      ".kernel",
+      "",
+    ] {
+      let error = key_to_path(Path::new("/proc"), key).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{}", key);
+    }
+    assert_eq!(
+      read(&fixture(), "kernel.//.hostname").unwrap_err().kind(),
+      io::ErrorKind::InvalidInput
+    );
+    assert!(write(&fixture(), "kernel.//.//.etc", "x").is_err());
+  }
+
+  #[test]
+  fn walks_the_fixture() {
+    let parameters = read_all(&fixture());
+    let keys: Vec<&str> = parameters.iter().map(|p| p.key.as_str()).collect();
+    assert_eq!(
+      keys,
+      [
+        "fs.file-max",
+        "kernel.hostname",
+        "kernel.pid_max",
+        "kernel.printk",
+        "kernel.tainted",
+        "net.ipv4.conf.eth0/1.forwarding",
+        "net.ipv4.ip_forward",
+        "vm.swappiness",
+      ]
+    );
+    assert_eq!(read(&fixture(), "kernel.printk").unwrap(), "4 4 1 7");
+  }
+
+  #[test]
+  fn conf_files() {
+    let settings = parse_conf(
+      "# comment\n; also a comment\n\nvm.swappiness = 10\n-net/ipv4/ip_forward=1\nkernel.printk =\t3 4 1 3\nnot a setting\n-kernel.no_such = 1\n",
+    );
+    assert_eq!(
+      settings,
+      [
+        Parameter {
+          key: "vm.swappiness".to_string(),
+          value: "10".to_string(),
+          optional: false,
+        },
+        Parameter {
+          key: "net.ipv4.ip_forward".to_string(),
+          value: "1".to_string(),
+          optional: true,
+        },
+        Parameter {
+          key: "kernel.printk".to_string(),
+          value: "3 4 1 3".to_string(),
+          optional: false,
+        },
+        Parameter {
+          key: "kernel.no_such".to_string(),
+          value: "1".to_string(),
+          optional: true,
+        },
+      ]
+    );
+
+    let differences = diff(&fixture(), &settings);
+    let keys: Vec<(&str, Option<&str>)> = differences
+      .iter()
+      .map(|d| (d.key.as_str(), d.current.as_deref()))
+      .collect();
+    assert_eq!(
+      keys,
+      [
+        ("vm.swappiness", Some("60")),
+        ("kernel.printk", Some("4 4 1 7"))
+      ]
+    );
+  }
+}
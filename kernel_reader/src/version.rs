@@ -0,0 +1,136 @@
+use serde::Serialize;
+
+/// The parts of /proc/version, e.g.
+/// "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-075) (x86_64-linux-gnu-gcc-13
+/// (Ubuntu 13.2.0-23ubuntu4) 13.2.0, GNU ld (GNU Binutils for Ubuntu) 2.42)
+/// #45-Ubuntu SMP PREEMPT_DYNAMIC Fri Aug 30 12:02:04 UTC 2024"
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct KernelVersion {
+  pub release: String,
+  /// user@host that built the kernel
+  pub build_host: String,
+  pub compiler: String,
+  /// Kernels before 5.8 only name the compiler
+  pub linker: Option<String>,
+  /// The build number and configuration, like "#45-Ubuntu SMP PREEMPT_DYNAMIC"
+  pub build: String,
+  /// As the build system wrote it; "@<seconds>" for reproducible builds
+  pub build_date: String,
+}
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+impl KernelVersion {
+  pub fn parse(line: &str) -> Option<KernelVersion> {
+    let rest = line.trim().strip_prefix("Linux version ")?;
+    let (release, rest) = rest.split_once(' ')?;
+    let (build_host, rest) = parenthesized(rest)?;
+    let (toolchain, rest) = parenthesized(rest)?;
+
+    // "compiler, linker", where both may contain parentheses but only the
+    // top level comma separates them
+    let (compiler, linker) = match top_level_comma(toolchain) {
+      Some(index) => (
+        &toolchain[..index],
+        Some(toolchain[index + 1..].trim().to_string()),
+      ),
+      None => (toolchain, None),
+    };
+
+    // The date starts with a weekday or, for reproducible builds, '@'
+    let words: Vec<&str> = rest.split_whitespace().collect();
+    let date_start = words
+      .iter()
+      .position(|word| WEEKDAYS.contains(word) || word.starts_with('@'))
+      .unwrap_or(words.len());
+
+    Some(KernelVersion {
+      release: release.to_string(),
+      build_host: build_host.to_string(),
+      compiler: compiler.trim().to_string(),
+      linker,
+      build: words[..date_start].join(" "),
+      build_date: words[date_start..].join(" "),
+    })
+  }
+}
+
+// The text inside the parentheses at the start of `text` and what follows them
+fn parenthesized(text: &str) -> Option<(&str, &str)> {
+  let text = text.trim_start().strip_prefix('(')?;
+  let mut depth = 1;
+  for (index, c) in text.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => {
+        depth -= 1;
+        if depth == 0 {
+          return Some((&text[..index], &text[index + 1..]));
+        }
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+fn top_level_comma(text: &str) -> Option<usize> {
+  let mut depth = 0;
+  for (index, c) in text.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      ',' if depth == 0 => return Some(index),
+      _ => {}
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ubuntu_gcc_build() {
+    let version = KernelVersion::parse(
+      "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-075) (x86_64-linux-gnu-gcc-13 (Ubuntu 13.2.0-23ubuntu4) 13.2.0, GNU ld (GNU Binutils for Ubuntu) 2.42) #45-Ubuntu SMP PREEMPT_DYNAMIC Fri Aug 30 12:02:04 UTC 2024\n",
+    )
+    .unwrap();
+    assert_eq!(
+      version,
+      KernelVersion {
+        release: "6.8.0-45-generic".to_string(),
+        build_host: "buildd@lcy02-amd64-075".to_string(),
+        compiler: "x86_64-linux-gnu-gcc-13 (Ubuntu 13.2.0-23ubuntu4) 13.2.0".to_string(),
+        linker: Some("GNU ld (GNU Binutils for Ubuntu) 2.42".to_string()),
+        build: "#45-Ubuntu SMP PREEMPT_DYNAMIC".to_string(),
+        build_date: "Fri Aug 30 12:02:04 UTC 2024".to_string(),
+      }
+    );
+  }
+
+  #[test]
+  fn old_and_reproducible_builds() {
+    let old = KernelVersion::parse(
+      "Linux version 4.4.0-210-generic (buildd@lgw01-amd64-009) (gcc version 5.4.0 20160609 (Ubuntu 5.4.0-6ubuntu1~16.04.12) ) #242-Ubuntu SMP Fri Apr 16 09:57:56 UTC 2021",
+    )
+    .unwrap();
+    assert_eq!(
+      old.compiler,
+      "gcc version 5.4.0 20160609 (Ubuntu 5.4.0-6ubuntu1~16.04.12)"
+    );
+    assert_eq!(old.linker, None);
+    assert_eq!(old.build, "#242-Ubuntu SMP");
+
+    let reproducible = KernelVersion::parse(
+      "Linux version 6.18.44 (builder@sandbox) (gcc (GCC) 15.3.0, GNU ld (GNU Binutils) 2.46) #1 SMP PREEMPT_DYNAMIC @0",
+    )
+    .unwrap();
+    assert_eq!(reproducible.build, "#1 SMP PREEMPT_DYNAMIC");
+    assert_eq!(reproducible.build_date, "@0");
+
+    assert_eq!(KernelVersion::parse("FreeBSD 14.0-RELEASE"), None);
+    assert_eq!(KernelVersion::parse("Linux version 6.8.0 (unclosed"), None);
+  }
+}
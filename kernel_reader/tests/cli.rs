@@ -0,0 +1,277 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture() -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc")
+}
+
+#[test]
+fn reads_the_version_from_the_proc_root() {
+  for output in [
+    Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+      .arg("--proc-root")
+      .arg(fixture())
+      .output()
+      .unwrap(),
+    Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+      .env("PROC_ROOT", fixture())
+      .output()
+      .unwrap(),
+  ] {
+    assert!(output.status.success());
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("| Release "));
+    assert!(text.contains("| 6.8.0-45-generic "));
+    assert!(text.contains("| buildd@lcy02-amd64-075 "));
+    assert!(text.contains("| #45-Ubuntu SMP PREEMPT_DYNAMIC "));
+    assert!(text.contains(" root=UUID=0b5a9f3e-1c2d-4e5f-8a9b-0c1d2e3f4a5b ro quiet "));
+  }
+}
+
+#[test]
+fn prints_json() {
+  let output = Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+    .arg("--proc-root")
+    .arg(fixture())
+    .args(["--format", "json"])
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+  assert_eq!(info["version"]["release"], "6.8.0-45-generic");
+  assert_eq!(
+    info["version"]["linker"],
+    "GNU ld (GNU Binutils for Ubuntu) 2.42"
+  );
+  assert_eq!(
+    info["version"]["build_date"],
+    "Fri Aug 30 12:02:04 UTC 2024"
+  );
+  assert_eq!(info["boot_time"], 1700000000);
+  assert!(
+    info["banner"]
+      .as_str()
+      .unwrap()
+      .starts_with("Linux version 6.8.0-45-generic")
+  );
+  // uname describes the machine running the test, not the fixture
+  assert_eq!(info["uname"]["sysname"], "Linux");
+}
+
+#[test]
+fn missing_version_is_an_error() {
+  let output = Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+    .args(["--proc-root", "/nonexistent/proc"])
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+}
+
+fn kernel_reader(args: &[&str]) -> String {
+  let output = Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+    .arg("--proc-root")
+    .arg(fixture())
+    .arg("--sys-root")
+    .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sys"))
+    .args(args)
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cpus_with_topology_and_caches() {
+  let table = kernel_reader(&["cpu"]);
+  assert!(table.contains("| AMD Ryzen 7 5800U with Radeon Graphics | 4506 |"));
+  assert!(table.contains("L1d 32K, L1i 32K, L2 512K, L3 16384K"));
+  assert!(table.contains("2 logical CPUs, 1 cores, 1 packages"));
+  assert!(table.contains("\nfpu vme de pse tsc msr sse sse2 ht avx avx2\n"));
+  assert!(table.contains("CPU 1 also: rdpid"));
+
+  let cpus: serde_json::Value =
+    serde_json::from_str(&kernel_reader(&["cpu", "-f", "json"])).unwrap();
+  assert_eq!(cpus[1]["mhz"], 4505.971);
+  assert_eq!(cpus[1]["topology"]["siblings"], "0-1");
+  assert_eq!(cpus[0]["caches"][3]["level"], 3);
+}
+
+#[test]
+fn memory_in_human_units() {
+  let table = kernel_reader(&["mem"]);
+  assert!(
+    table.contains("| Total         |  15.3 GiB |   100.0% |"),
+    "{}",
+    table
+  );
+  assert!(table.contains("| Available     |  11.4 GiB |    75.0% |"));
+  assert!(table.contains("Swap: 976.6 MiB of 3.8 GiB used (25.0%)"));
+
+  let mem: serde_json::Value =
+    serde_json::from_str(&kernel_reader(&["mem", "-f", "json"])).unwrap();
+  assert_eq!(mem["used"], 4000000);
+  assert_eq!(mem["available_percent"], 75.0);
+}
+
+#[test]
+fn sysctl_list_and_diff() {
+  let table = kernel_reader(&["sysctl", "list", "^net\\."]);
+  assert!(table.contains("| net.ipv4.conf.eth0/1.forwarding | 0     |"));
+  assert!(!table.contains("vm.swappiness"));
+
+  let conf_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysctl.d");
+  let diff = kernel_reader(&["sysctl", "diff", "--conf-dir", conf_dir.to_str().unwrap()]);
+  assert!(diff.contains("| vm.swappiness                   | 10       | 60      |"));
+  // Marked optional with a leading '-', so its absence is not a difference
+  assert!(!diff.contains("kernel.unknown_setting"));
+  // Overridden by 99-local.conf to the current value
+  assert!(!diff.contains("net.ipv4.ip_forward"));
+  assert!(diff.contains("2 of 5 settings differ"));
+
+  // A saved listing is a baseline without differences
+  let baseline =
+    std::env::temp_dir().join(format!("kernel_reader_baseline_{}", std::process::id()));
+  std::fs::write(&baseline, kernel_reader(&["sysctl", "list", "--conf"])).unwrap();
+  let diff = kernel_reader(&["sysctl", "diff", baseline.to_str().unwrap()]);
+  std::fs::remove_file(&baseline).unwrap();
+  assert_eq!(diff, "All 8 settings match\n");
+}
+
+#[test]
+fn sysctl_set_writes_unless_dry_run() {
+  let root = std::env::temp_dir().join(format!("kernel_reader_proc_{}", std::process::id()));
+  std::fs::create_dir_all(root.join("sys/vm")).unwrap();
+  std::fs::write(root.join("sys/vm/swappiness"), "60\n").unwrap();
+  let set = |args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+      .arg("--proc-root")
+      .arg(&root)
+      .args(["sysctl", "set"])
+      .args(args)
+      .output()
+      .unwrap()
+  };
+
+  let dry_run = set(&["vm.swappiness=10", "--dry-run"]);
+  assert!(dry_run.status.success());
+  assert!(String::from_utf8_lossy(&dry_run.stdout).contains("| vm.swappiness | 60      | 10  |"));
+  assert_eq!(
+    std::fs::read_to_string(root.join("sys/vm/swappiness")).unwrap(),
+    "60\n"
+  );
+
+  assert!(set(&["vm.swappiness=10"]).status.success());
+  assert_eq!(
+    std::fs::read_to_string(root.join("sys/vm/swappiness")).unwrap(),
+    "10\n"
+  );
+
+  // Nothing is written when any assignment is invalid
+  let invalid = set(&["vm.swappiness=30", "vm.no_such_key=1"]);
+  assert!(!invalid.status.success());
+  assert_eq!(
+    std::fs::read_to_string(root.join("sys/vm/swappiness")).unwrap(),
+    "10\n"
+  );
+  assert!(!root.join("sys/vm/no_such_key").exists());
+
+  // A value the kernel refuses undoes the assignments written before it
+  std::os::unix::fs::symlink("/proc/version", root.join("sys/vm/read_only")).unwrap();
+  let refused = set(&["vm.swappiness=30", "vm.read_only=1"]);
+  assert!(!refused.status.success());
+  assert!(String::from_utf8_lossy(&refused.stderr).contains("Restored vm.swappiness = 10"));
+  assert_eq!(
+    std::fs::read_to_string(root.join("sys/vm/swappiness")).unwrap(),
+    "10\n"
+  );
+
+  // Slash-separated keys name the same parameter; empty values and keys
+  // leaving /proc/sys are refused
+  assert!(set(&["vm/swappiness=20"]).status.success());
+  assert_eq!(
+    std::fs::read_to_string(root.join("sys/vm/swappiness")).unwrap(),
+    "20\n"
+  );
+  assert!(!set(&["vm.swappiness= "]).status.success());
+  std::fs::write(root.join("outside"), "0\n").unwrap();
+  assert!(!set(&["vm.//.//.outside=1"]).status.success());
+  assert_eq!(
+    std::fs::read_to_string(root.join("outside")).unwrap(),
+    "0\n"
+  );
+  assert_eq!(
+    std::fs::read_to_string(root.join("sys/vm/swappiness")).unwrap(),
+    "20\n"
+  );
+
+  std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn modules_table_tree_and_taint() {
+  let table = kernel_reader(&["modules", "--parameters"]);
+  assert!(table.contains("| nvidia "), "{}", table);
+  assert!(
+    table.contains("| nvidia_uvm, nvidia_modeset | Live  | proprietary, out-of-tree, unsigned |")
+  );
+  assert!(table.contains("power_save=1"));
+  assert!(table.contains("The kernel is tainted: 12289 (POE)"));
+  assert!(table.contains("  O (bit 12): externally-built (\"out-of-tree\") module was loaded"));
+
+  let tree = kernel_reader(&["modules", "--tree"]);
+  assert!(
+    tree.contains(
+      "nvidia_drm\n├─ drm\n├─ drm_kms_helper\n│  └─ drm\n└─ nvidia_modeset\n   └─ nvidia\n"
+    ),
+    "{}",
+    tree
+  );
+  let reverse = kernel_reader(&["modules", "--tree", "--reverse"]);
+  assert!(
+    reverse.starts_with("drm\n├─ drm_kms_helper\n│  └─ nvidia_drm\n└─ nvidia_drm\n"),
+    "{}",
+    reverse
+  );
+
+  let json: serde_json::Value =
+    serde_json::from_str(&kernel_reader(&["modules", "-f", "json"])).unwrap();
+  assert_eq!(json["tainted"], 12289);
+  assert_eq!(json["taint_flags"], serde_json::json!(["P", "O", "E"]));
+  let nvidia = &json["modules"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .find(|m| m["name"] == "nvidia")
+    .unwrap();
+  assert_eq!(nvidia["version"], "550.107.02");
+  assert_eq!(
+    nvidia["parameters"]["NVreg_PreserveVideoMemoryAllocations"],
+    "1"
+  );
+}
+
+#[test]
+fn taint_without_module_support() {
+  let root = std::env::temp_dir().join(format!("kernel_reader_nomodules_{}", std::process::id()));
+  std::fs::create_dir_all(root.join("sys/kernel")).unwrap();
+  // Proprietary, and bit 20 which the table does not know
+  std::fs::write(root.join("sys/kernel/tainted"), "1048577\n").unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_kernel_reader"))
+    .arg("--proc-root")
+    .arg(&root)
+    .arg("modules")
+    .output()
+    .unwrap();
+  std::fs::remove_dir_all(&root).unwrap();
+
+  assert!(output.status.success());
+  let text = String::from_utf8_lossy(&output.stdout);
+  assert!(text.contains("This kernel was built without loadable module support"));
+  assert!(text.contains("The kernel is tainted: 1048577 (P)"));
+  assert!(text.contains("  P (bit 0): proprietary module was loaded"));
+  assert!(text.contains("  unknown bit 20"));
+}
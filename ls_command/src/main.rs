@@ -1,60 +1,85 @@
+use ls_common::{DirEntryInfo, Output, format_mode};
 use std::env;
-use std::fs::{self, DirEntry};
 use std::io;
-use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
+use tabled::Tabled;
 
-// Function to format file permissions in Unix style (e.g., -rwxr-xr-x)
-fn format_permissions(perms: &fs::Permissions) -> String {
-  let mode = perms.mode();
-  let user = [
-    (mode & 0o400, 'r'),
-    (mode & 0o200, 'w'),
-    (mode & 0o100, 'x'),
-  ];
-  let group = [
-    (mode & 0o040, 'r'),
-    (mode & 0o020, 'w'),
-    (mode & 0o010, 'x'),
-  ];
-  let other = [
-    (mode & 0o004, 'r'),
-    (mode & 0o002, 'w'),
-    (mode & 0o001, 'x'),
-  ];
+mod xattrs;
 
-  let mut result = String::new();
-  result.push(if perms.mode() & 0o100000 != 0 {
-    '-'
-  } else {
-    'd'
-  }); // File or directory
-  for &(bit, ch) in user.iter().chain(group.iter()).chain(other.iter()) {
-    result.push(if bit != 0 { ch } else { '-' });
-  }
-  result
-}
+use xattrs::Attributes;
 
-// Function to process and display a single directory entry
-fn process_entry(entry: io::Result<DirEntry>) -> io::Result<()> {
-  let entry = entry?;
-  let path = entry.path();
-  let metadata = entry.metadata()?;
+// Output options from the command-line flags
+struct Options {
+  // -@: mark entries carrying xattrs, ACLs or capabilities
+  markers: bool,
+  // -e: decode and print those attributes below each entry
+  details: bool,
+}
 
-  // Get file type, permissions, and size
-  let file_type = if metadata.is_dir() { "dir" } else { "file" };
-  let permissions = format_permissions(&metadata.permissions());
-  let size = metadata.len();
-  let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("???");
+// One line of the listing
+#[derive(Tabled)]
+struct Row {
+  #[tabled(rename = "Permissions")]
+  permissions: String,
+  #[tabled(rename = "Size")]
+  size: u64,
+  #[tabled(rename = "Type")]
+  file_type: &'static str,
+  #[tabled(rename = "Name")]
+  name: String,
+}
 
-  // Print formatted output
-  println!("{} {:>10} {} {}", permissions, size, file_type, file_name);
-  Ok(())
+// Build the listing line of a single directory entry
+fn entry_row(
+  info: &DirEntryInfo,
+  attributes: &Option<io::Result<Attributes>>,
+  options: &Options,
+) -> Row {
+  // When the attributes cannot be read the entry is still listed, marked with '?'
+  let markers = match (attributes, options.markers) {
+    (Some(Ok(attributes)), true) => attributes.markers(),
+    (Some(Err(_)), true) => "?".to_string(),
+    _ => String::new(),
+  };
+  Row {
+    permissions: format!("{}{}", format_mode(info.mode), markers),
+    size: info.size,
+    file_type: info.kind.label(),
+    name: info.name.clone(),
+  }
 }
 
 fn main() -> io::Result<()> {
-  // Get directory from command-line argument or use current directory
-  let dir_path = env::args().nth(1).unwrap_or_else(|| ".".to_string());
+  let mut options = Options {
+    markers: false,
+    details: false,
+  };
+  let mut dir_path = None;
+
+  // Parse flags (e.g. -l@ or -e) and the directory argument
+  for arg in env::args().skip(1) {
+    match arg.strip_prefix('-') {
+      Some(flags) if !flags.is_empty() => {
+        for flag in flags.chars() {
+          match flag {
+            // The listing is always long, -l is accepted for familiarity
+            'l' => {}
+            '@' => options.markers = true,
+            'e' => options.details = true,
+            _ => {
+              eprintln!("Error: unknown flag '-{}'", flag);
+              eprintln!("Usage: ls_command [-l@e] [directory]");
+              std::process::exit(1);
+            }
+          }
+        }
+      }
+      _ => dir_path = Some(arg),
+    }
+  }
+
+  // Use the current directory when none was given
+  let dir_path = dir_path.unwrap_or_else(|| ".".to_string());
   let path = Path::new(&dir_path);
 
   // Check if the path is a directory
@@ -63,10 +88,30 @@ fn main() -> io::Result<()> {
     std::process::exit(1);
   }
 
-  // Read directory entries and process them
-  for entry in fs::read_dir(path)? {
-    if let Err(e) = process_entry(entry) {
-      eprintln!("Error processing entry: {}", e);
+  // Extended attributes are only read when they are asked for
+  let entries = ls_common::read_dir(path)?;
+  let attributes: Vec<Option<io::Result<Attributes>>> = entries
+    .iter()
+    .map(|info| (options.markers || options.details).then(|| Attributes::read(&info.path)))
+    .collect();
+  let rows: Vec<Row> = entries
+    .iter()
+    .zip(&attributes)
+    .map(|(info, attributes)| entry_row(info, attributes, &options))
+    .collect();
+
+  // Output::Long prints one line per row, so the details can follow their entry
+  let listing = Output::Long.render(&rows);
+  for (line, attributes) in listing.lines().zip(&attributes) {
+    println!("{}", line);
+    match (attributes, options.details) {
+      (Some(Ok(attributes)), true) => {
+        for line in attributes.details() {
+          println!("    {}", line);
+        }
+      }
+      (Some(Err(e)), true) => println!("    attributes unreadable: {}", e),
+      _ => {}
     }
   }
 
@@ -0,0 +1,365 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const ACL_ACCESS: &str = "system.posix_acl_access";
+const ACL_DEFAULT: &str = "system.posix_acl_default";
+const CAPABILITY: &str = "security.capability";
+
+// ACL entry tags from <linux/posix_acl.h>
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+
+// Capability set layout from <linux/capability.h>
+const VFS_CAP_REVISION_MASK: u32 = 0xff00_0000;
+const VFS_CAP_REVISION_1: u32 = 0x0100_0000;
+const VFS_CAP_REVISION_2: u32 = 0x0200_0000;
+const VFS_CAP_REVISION_3: u32 = 0x0300_0000;
+const VFS_CAP_FLAGS_EFFECTIVE: u32 = 0x0000_0001;
+
+// Capability names, indexed by capability number
+const CAP_NAMES: [&str; 41] = [
+  "cap_chown",
+  "cap_dac_override",
+  "cap_dac_read_search",
+  "cap_fowner",
+  "cap_fsetid",
+  "cap_kill",
+  "cap_setgid",
+  "cap_setuid",
+  "cap_setpcap",
+  "cap_linux_immutable",
+  "cap_net_bind_service",
+  "cap_net_broadcast",
+  "cap_net_admin",
+  "cap_net_raw",
+  "cap_ipc_lock",
+  "cap_ipc_owner",
+  "cap_sys_module",
+  "cap_sys_rawio",
+  "cap_sys_chroot",
+  "cap_sys_ptrace",
+  "cap_sys_pacct",
+  "cap_sys_admin",
+  "cap_sys_boot",
+  "cap_sys_nice",
+  "cap_sys_resource",
+  "cap_sys_time",
+  "cap_sys_tty_config",
+  "cap_mknod",
+  "cap_lease",
+  "cap_audit_write",
+  "cap_audit_control",
+  "cap_setfcap",
+  "cap_mac_override",
+  "cap_mac_admin",
+  "cap_syslog",
+  "cap_wake_alarm",
+  "cap_block_suspend",
+  "cap_audit_read",
+  "cap_perfmon",
+  "cap_bpf",
+  "cap_checkpoint_restore",
+];
+
+// Extended attributes of a single file
+pub struct Attributes {
+  // Attribute names with the size of their values
+  pub names: Vec<(String, usize)>,
+  acl_access: Option<Vec<u8>>,
+  acl_default: Option<Vec<u8>>,
+  capability: Option<Vec<u8>>,
+}
+
+impl Attributes {
+  // Read the attributes of `path` without following symlinks
+  pub fn read(path: &Path) -> io::Result<Attributes> {
+    let mut attributes = Attributes {
+      names: Vec::new(),
+      acl_access: None,
+      acl_default: None,
+      capability: None,
+    };
+
+    // Filesystems without xattr support simply have none
+    let names = match xattr::list(path) {
+      Ok(names) => names,
+      Err(e) if e.kind() == io::ErrorKind::Unsupported => return Ok(attributes),
+      Err(e) => return Err(e),
+    };
+
+    for name in names {
+      let name = name.to_string_lossy().into_owned();
+      let value = xattr::get(path, &name)?.unwrap_or_default();
+      attributes.names.push((name.clone(), value.len()));
+      match name.as_str() {
+        ACL_ACCESS => attributes.acl_access = Some(value),
+        ACL_DEFAULT => attributes.acl_default = Some(value),
+        CAPABILITY => attributes.capability = Some(value),
+        _ => {}
+      }
+    }
+    Ok(attributes)
+  }
+
+  // Markers shown after the permissions: '@' xattrs, '+' ACL, 'c' capabilities
+  pub fn markers(&self) -> String {
+    let mut markers = String::new();
+    if !self.names.is_empty() {
+      markers.push('@');
+    }
+    if self.acl_access.is_some() || self.acl_default.is_some() {
+      markers.push('+');
+    }
+    if self.capability.is_some() {
+      markers.push('c');
+    }
+    markers
+  }
+
+  // Decoded details, one line each, for the detail mode
+  pub fn details(&self) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (name, size) in &self.names {
+      lines.push(format!("xattr {} ({} bytes)", name, size));
+    }
+    if let Some(acl) = &self.acl_access {
+      lines.extend(
+        decode_acl(acl)
+          .into_iter()
+          .map(|entry| format!("acl {}", entry)),
+      );
+    }
+    if let Some(acl) = &self.acl_default {
+      lines.extend(
+        decode_acl(acl)
+          .into_iter()
+          .map(|entry| format!("acl default:{}", entry)),
+      );
+    }
+    if let Some(capability) = &self.capability {
+      lines.push(format!("capabilities {}", decode_capability(capability)));
+    }
+    lines
+  }
+}
+
+// Decode a POSIX ACL xattr into getfacl style entries (e.g. "user:1000:rw-")
+fn decode_acl(value: &[u8]) -> Vec<String> {
+  // A 4 byte version header, then 8 byte entries: u16 tag, u16 perm, u32 id
+  value
+    .get(4..)
+    .unwrap_or_default()
+    .chunks_exact(8)
+    .map(|entry| {
+      let tag = u16::from_le_bytes([entry[0], entry[1]]);
+      let perm = u16::from_le_bytes([entry[2], entry[3]]);
+      let id = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+
+      let qualifier = match tag {
+        ACL_USER => lookup_name("/etc/passwd", id),
+        ACL_GROUP => lookup_name("/etc/group", id),
+        _ => String::new(),
+      };
+      let kind = match tag {
+        ACL_USER_OBJ | ACL_USER => "user",
+        ACL_GROUP_OBJ | ACL_GROUP => "group",
+        ACL_MASK => "mask",
+        ACL_OTHER => "other",
+        _ => "unknown",
+      };
+      let rwx: String = [(4, 'r'), (2, 'w'), (1, 'x')]
+        .iter()
+        .map(|&(bit, ch)| if perm & bit != 0 { ch } else { '-' })
+        .collect();
+      format!("{}:{}:{}", kind, qualifier, rwx)
+    })
+    .collect()
+}
+
+// Decode a security.capability xattr the way getcap prints it (e.g. "cap_net_raw=ep")
+fn decode_capability(value: &[u8]) -> String {
+  let words: Vec<u32> = value
+    .chunks_exact(4)
+    .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    .collect();
+  let Some(&magic) = words.first() else {
+    return "(empty)".to_string();
+  };
+
+  // Revision 1 stores one 32 bit permitted/inheritable pair, later revisions store two.
+  // Revision 3 adds the root user id of the file's user namespace.
+  let (pairs, length) = match magic & VFS_CAP_REVISION_MASK {
+    VFS_CAP_REVISION_1 => (1, 12),
+    VFS_CAP_REVISION_2 => (2, 20),
+    VFS_CAP_REVISION_3 => (2, 24),
+    revision => return format!("(unknown revision {})", revision >> 24),
+  };
+  if value.len() < length {
+    return "(truncated)".to_string();
+  }
+  let mut permitted: u64 = 0;
+  let mut inheritable: u64 = 0;
+  for pair in 0..pairs {
+    permitted |= (words[1 + pair * 2] as u64) << (32 * pair);
+    inheritable |= (words[2 + pair * 2] as u64) << (32 * pair);
+  }
+  let effective = magic & VFS_CAP_FLAGS_EFFECTIVE != 0;
+
+  // Group capabilities that share the same set of flags
+  let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+  for bit in 0..64 {
+    let in_permitted = permitted & (1 << bit) != 0;
+    let in_inheritable = inheritable & (1 << bit) != 0;
+    if !in_permitted && !in_inheritable {
+      continue;
+    }
+
+    let mut flags = String::new();
+    if effective && in_permitted {
+      flags.push('e');
+    }
+    if in_inheritable {
+      flags.push('i');
+    }
+    if in_permitted {
+      flags.push('p');
+    }
+
+    let name = CAP_NAMES
+      .get(bit)
+      .map(|name| name.to_string())
+      .unwrap_or_else(|| format!("cap_{}", bit));
+    match groups
+      .iter_mut()
+      .find(|(group_flags, _)| *group_flags == flags)
+    {
+      Some((_, names)) => names.push(name),
+      None => groups.push((flags, vec![name])),
+    }
+  }
+
+  groups
+    .iter()
+    .map(|(flags, names)| format!("{}={}", names.join(","), flags))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+// Find the name for an id in /etc/passwd or /etc/group, falling back to the number
+fn lookup_name(database: &str, id: u32) -> String {
+  let id = id.to_string();
+  fs::read_to_string(database)
+    .ok()
+    .and_then(|content| {
+      content.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() > 2 && fields[2] == id).then(|| fields[0].to_string())
+      })
+    })
+    .unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn words(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+  }
+
+  fn acl(entries: &[(u16, u16, u32)]) -> Vec<u8> {
+    let mut value = 2u32.to_le_bytes().to_vec();
+    for &(tag, perm, id) in entries {
+      value.extend(tag.to_le_bytes());
+      value.extend(perm.to_le_bytes());
+      value.extend(id.to_le_bytes());
+    }
+    value
+  }
+
+  #[test]
+  fn decodes_acl_entries() {
+    // What setfacl -m u:4242:rw,g:root:r writes on a 0640 file
+    let value = acl(&[
+      (ACL_USER_OBJ, 6, u32::MAX),
+      (ACL_USER, 6, 4242),
+      (ACL_GROUP_OBJ, 4, u32::MAX),
+      (ACL_GROUP, 4, 0),
+      (ACL_MASK, 6, u32::MAX),
+      (ACL_OTHER, 0, u32::MAX),
+    ]);
+    assert_eq!(
+      decode_acl(&value),
+      [
+        "user::rw-",
+        "user:4242:rw-",
+        "group::r--",
+        "group:root:r--",
+        "mask::rw-",
+        "other::---",
+      ]
+    );
+  }
+
+  #[test]
+  fn ignores_truncated_acl_entries() {
+    let value = acl(&[(ACL_USER_OBJ, 7, u32::MAX), (ACL_OTHER, 5, u32::MAX)]);
+    assert_eq!(decode_acl(&value[..value.len() - 3]), ["user::rwx"]);
+    assert!(decode_acl(&value[..4]).is_empty());
+    assert!(decode_acl(&value[..2]).is_empty());
+    assert!(decode_acl(&[]).is_empty());
+  }
+
+  #[test]
+  fn decodes_v2_capabilities() {
+    // setcap cap_net_raw,cap_net_admin=ep
+    let raw = 1 << 13 | 1 << 12;
+    let value = words(&[VFS_CAP_REVISION_2 | VFS_CAP_FLAGS_EFFECTIVE, raw, 0, 0, 0]);
+    assert_eq!(decode_capability(&value), "cap_net_admin,cap_net_raw=ep");
+
+    // setcap cap_chown=p cap_bpf=ip, without the effective flag
+    let value = words(&[VFS_CAP_REVISION_2, 1, 0, 1 << (39 - 32), 1 << (39 - 32)]);
+    assert_eq!(decode_capability(&value), "cap_chown=p cap_bpf=ip");
+  }
+
+  #[test]
+  fn decodes_v1_and_v3_capabilities() {
+    let value = words(&[VFS_CAP_REVISION_1 | VFS_CAP_FLAGS_EFFECTIVE, 1 << 10, 0]);
+    assert_eq!(decode_capability(&value), "cap_net_bind_service=ep");
+
+    // Revision 3 ends with the namespace root id
+    let value = words(&[VFS_CAP_REVISION_3, 0, 1 << 21, 0, 0, 100000]);
+    assert_eq!(decode_capability(&value), "cap_sys_admin=i");
+
+    // Numbers beyond the known names
+    let value = words(&[VFS_CAP_REVISION_2, 0, 0, 1 << (50 - 32), 0]);
+    assert_eq!(decode_capability(&value), "cap_50=p");
+  }
+
+  #[test]
+  fn rejects_truncated_capabilities() {
+    let value = words(&[
+      VFS_CAP_REVISION_2 | VFS_CAP_FLAGS_EFFECTIVE,
+      1 << 13,
+      0,
+      0,
+      0,
+    ]);
+    assert_eq!(decode_capability(&value[..19]), "(truncated)");
+    assert_eq!(decode_capability(&value[..4]), "(truncated)");
+    assert_eq!(decode_capability(&value[..3]), "(empty)");
+    assert_eq!(
+      decode_capability(&words(&[VFS_CAP_REVISION_3, 0, 0, 0, 0])),
+      "(truncated)"
+    );
+    assert_eq!(
+      decode_capability(&words(&[0x0900_0000, 0, 0])),
+      "(unknown revision 9)"
+    );
+  }
+}
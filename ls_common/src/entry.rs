@@ -0,0 +1,170 @@
+use std::fs::{self, DirEntry, Metadata};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bit mask selecting the file type from `st_mode`
+const S_IFMT: u32 = 0o170000;
+
+/// The type of a directory entry, as encoded in the upper bits of its mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+  File,
+  Dir,
+  Symlink,
+  Fifo,
+  Socket,
+  CharDevice,
+  BlockDevice,
+}
+
+impl EntryKind {
+  /// Decode the file type bits of a raw mode (`st_mode`)
+  pub fn from_mode(mode: u32) -> EntryKind {
+    match mode & S_IFMT {
+      0o040000 => EntryKind::Dir,
+      0o120000 => EntryKind::Symlink,
+      0o010000 => EntryKind::Fifo,
+      0o140000 => EntryKind::Socket,
+      0o020000 => EntryKind::CharDevice,
+      0o060000 => EntryKind::BlockDevice,
+      _ => EntryKind::File,
+    }
+  }
+
+  /// The first character of `ls -l` output (e.g. 'd' for directories)
+  pub fn type_char(self) -> char {
+    match self {
+      EntryKind::File => '-',
+      EntryKind::Dir => 'd',
+      EntryKind::Symlink => 'l',
+      EntryKind::Fifo => 'p',
+      EntryKind::Socket => 's',
+      EntryKind::CharDevice => 'c',
+      EntryKind::BlockDevice => 'b',
+    }
+  }
+
+  /// A short name for the Type column (e.g. "dir")
+  pub fn label(self) -> &'static str {
+    match self {
+      EntryKind::File => "file",
+      EntryKind::Dir => "dir",
+      EntryKind::Symlink => "symlink",
+      EntryKind::Fifo => "fifo",
+      EntryKind::Socket => "socket",
+      EntryKind::CharDevice => "char",
+      EntryKind::BlockDevice => "block",
+    }
+  }
+}
+
+/// Everything the listing tools know about a single directory entry.
+/// Symlinks are described themselves, not their targets.
+#[derive(Clone, Debug)]
+pub struct DirEntryInfo {
+  pub path: PathBuf,
+  pub name: String,
+  pub kind: EntryKind,
+  pub size: u64,
+  /// The full `st_mode`, including the file type bits
+  pub mode: u32,
+  pub modified: SystemTime,
+  pub uid: u32,
+  pub gid: u32,
+  pub nlink: u64,
+  pub dev: u64,
+  pub ino: u64,
+}
+
+impl DirEntryInfo {
+  /// Build the entry from already fetched metadata
+  pub fn from_metadata(path: &Path, metadata: &Metadata) -> DirEntryInfo {
+    DirEntryInfo {
+      path: path.to_path_buf(),
+      name: path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default(),
+      kind: EntryKind::from_mode(metadata.mode()),
+      size: metadata.len(),
+      mode: metadata.mode(),
+      modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+      uid: metadata.uid(),
+      gid: metadata.gid(),
+      nlink: metadata.nlink(),
+      dev: metadata.dev(),
+      ino: metadata.ino(),
+    }
+  }
+
+  /// Describe an entry returned by `fs::read_dir`
+  pub fn from_dir_entry(entry: &DirEntry) -> io::Result<DirEntryInfo> {
+    Ok(DirEntryInfo::from_metadata(
+      &entry.path(),
+      &entry.metadata()?,
+    ))
+  }
+
+  /// Describe the file at `path` without following a final symlink
+  pub fn read(path: &Path) -> io::Result<DirEntryInfo> {
+    Ok(DirEntryInfo::from_metadata(
+      path,
+      &fs::symlink_metadata(path)?,
+    ))
+  }
+
+  pub fn is_dir(&self) -> bool {
+    self.kind == EntryKind::Dir
+  }
+
+  pub fn is_file(&self) -> bool {
+    self.kind == EntryKind::File
+  }
+
+  pub fn is_symlink(&self) -> bool {
+    self.kind == EntryKind::Symlink
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_every_file_type() {
+    let cases = [
+      (0o100644, EntryKind::File, '-'),
+      (0o040755, EntryKind::Dir, 'd'),
+      (0o120777, EntryKind::Symlink, 'l'),
+      (0o010644, EntryKind::Fifo, 'p'),
+      (0o140755, EntryKind::Socket, 's'),
+      (0o020620, EntryKind::CharDevice, 'c'),
+      (0o060660, EntryKind::BlockDevice, 'b'),
+    ];
+    for (mode, kind, type_char) in cases {
+      assert_eq!(EntryKind::from_mode(mode), kind, "mode {:o}", mode);
+      assert_eq!(kind.type_char(), type_char);
+    }
+  }
+
+  #[test]
+  fn reads_entries_without_following_symlinks() {
+    let dir = std::env::temp_dir().join(format!("ls_common_entry_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("file.txt");
+    let link = dir.join("link");
+    fs::write(&file, "hello").unwrap();
+    std::os::unix::fs::symlink(&file, &link).unwrap();
+
+    let file_info = DirEntryInfo::read(&file).unwrap();
+    assert_eq!(file_info.name, "file.txt");
+    assert_eq!(file_info.size, 5);
+    assert!(file_info.is_file());
+    assert!(DirEntryInfo::read(&link).unwrap().is_symlink());
+    assert!(DirEntryInfo::read(&dir).unwrap().is_dir());
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
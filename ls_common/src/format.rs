@@ -0,0 +1,114 @@
+use chrono::{DateTime, Local};
+use std::time::SystemTime;
+
+use crate::entry::EntryKind;
+
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_ISVTX: u32 = 0o1000;
+
+/// Format the permission bits in Unix style (e.g. `rwxr-xr-x`).
+/// setuid, setgid and sticky show as `s`/`S` and `t`/`T` like `ls -l`.
+pub fn format_permissions(mode: u32) -> String {
+  let mut result = String::with_capacity(9);
+  result.push_str(&triplet(mode >> 6, mode & S_ISUID != 0, 's'));
+  result.push_str(&triplet(mode >> 3, mode & S_ISGID != 0, 's'));
+  result.push_str(&triplet(mode, mode & S_ISVTX != 0, 't'));
+  result
+}
+
+/// Format a full mode with its file type character first (e.g. `-rw-r--r--`, `drwxr-xr-x`)
+pub fn format_mode(mode: u32) -> String {
+  format!(
+    "{}{}",
+    EntryKind::from_mode(mode).type_char(),
+    format_permissions(mode)
+  )
+}
+
+/// Format a timestamp the way the listings show it (e.g. `2024-05-01 12:30:00`)
+pub fn format_time(time: SystemTime) -> String {
+  let time: DateTime<Local> = time.into();
+  time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// One rwx group. `special` replaces the x with `marker` (lowercase when x is also set).
+fn triplet(bits: u32, special: bool, marker: char) -> String {
+  let r = if bits & 0o4 != 0 { 'r' } else { '-' };
+  let w = if bits & 0o2 != 0 { 'w' } else { '-' };
+  let executable = bits & 0o1 != 0;
+  let x = match (special, executable) {
+    (true, true) => marker,
+    (true, false) => marker.to_ascii_uppercase(),
+    (false, true) => 'x',
+    (false, false) => '-',
+  };
+  [r, w, x].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn each_permission_bit_sets_one_character() {
+    let bits = [
+      (0o400, "r--------"),
+      (0o200, "-w-------"),
+      (0o100, "--x------"),
+      (0o040, "---r-----"),
+      (0o020, "----w----"),
+      (0o010, "-----x---"),
+      (0o004, "------r--"),
+      (0o002, "-------w-"),
+      (0o001, "--------x"),
+    ];
+    for (mode, expected) in bits {
+      assert_eq!(format_permissions(mode), expected, "mode {:o}", mode);
+    }
+  }
+
+  #[test]
+  fn common_modes() {
+    assert_eq!(format_permissions(0o000), "---------");
+    assert_eq!(format_permissions(0o644), "rw-r--r--");
+    assert_eq!(format_permissions(0o755), "rwxr-xr-x");
+    assert_eq!(format_permissions(0o777), "rwxrwxrwx");
+  }
+
+  #[test]
+  fn setuid_bit() {
+    assert_eq!(format_permissions(0o4755), "rwsr-xr-x");
+    assert_eq!(format_permissions(0o4644), "rwSr--r--");
+  }
+
+  #[test]
+  fn setgid_bit() {
+    assert_eq!(format_permissions(0o2755), "rwxr-sr-x");
+    assert_eq!(format_permissions(0o2745), "rwxr-Sr-x");
+  }
+
+  #[test]
+  fn sticky_bit() {
+    assert_eq!(format_permissions(0o1777), "rwxrwxrwt");
+    assert_eq!(format_permissions(0o1776), "rwxrwxrwT");
+  }
+
+  #[test]
+  fn file_type_bits_do_not_affect_permissions() {
+    assert_eq!(format_permissions(0o100644), "rw-r--r--");
+    assert_eq!(format_permissions(0o040755), "rwxr-xr-x");
+  }
+
+  #[test]
+  fn mode_includes_the_type_character() {
+    assert_eq!(format_mode(0o100644), "-rw-r--r--");
+    assert_eq!(format_mode(0o040755), "drwxr-xr-x");
+    assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+    assert_eq!(format_mode(0o010644), "prw-r--r--");
+    assert_eq!(format_mode(0o140755), "srwxr-xr-x");
+    assert_eq!(format_mode(0o020620), "crw--w----");
+    assert_eq!(format_mode(0o060660), "brw-rw----");
+    assert_eq!(format_mode(0o041777), "drwxrwxrwt");
+  }
+}
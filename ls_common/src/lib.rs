@@ -0,0 +1,13 @@
+//! Shared pieces of the directory listing tools (`ls_command`, `ls_table`):
+//! the [`DirEntryInfo`] model, directory traversal, permission formatting
+//! and the output backends that print the listings.
+
+pub mod entry;
+pub mod format;
+pub mod output;
+pub mod walk;
+
+pub use entry::{DirEntryInfo, EntryKind};
+pub use format::{format_mode, format_permissions, format_time};
+pub use output::Output;
+pub use walk::{read_dir, walk};
@@ -0,0 +1,180 @@
+use tabled::{
+  Tabled,
+  builder::Builder,
+  settings::{Alignment, Modify, Style, object::Rows},
+};
+
+/// How a listing is printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+  /// A boxed table with a header row
+  Table,
+  /// Whitespace separated columns with a header line, for scripts and pagers
+  Plain,
+  /// `ls -l` style lines without a header; numeric columns are right aligned
+  Long,
+}
+
+impl Output {
+  /// Render rows with all of their columns
+  pub fn render<T: Tabled>(&self, rows: &[T]) -> String {
+    self.render_without(rows, &[])
+  }
+
+  /// Render rows, leaving out the columns whose header is in `hidden`
+  pub fn render_without<T: Tabled>(&self, rows: &[T], hidden: &[&str]) -> String {
+    let headers = T::headers();
+    let visible: Vec<usize> = (0..headers.len())
+      .filter(|&column| !hidden.contains(&headers[column].as_ref()))
+      .collect();
+
+    let mut records: Vec<Vec<String>> = Vec::with_capacity(rows.len() + 1);
+    records.push(visible.iter().map(|&c| headers[c].to_string()).collect());
+    for row in rows {
+      let fields = row.fields();
+      records.push(visible.iter().map(|&c| fields[c].to_string()).collect());
+    }
+
+    match self {
+      Output::Table => render_table(records),
+      Output::Plain => render_plain(&records),
+      Output::Long => render_long(&records[1..]),
+    }
+  }
+}
+
+fn render_table(records: Vec<Vec<String>>) -> String {
+  let mut builder = Builder::new();
+  for record in records {
+    builder.push_record(record);
+  }
+  builder
+    .build()
+    .with(Style::modern())
+    .with(Modify::new(Rows::new(1..)).with(Alignment::left()))
+    .to_string()
+}
+
+fn render_plain(records: &[Vec<String>]) -> String {
+  let columns = records.first().map_or(0, Vec::len);
+  let widths: Vec<usize> = (0..columns)
+    .map(|c| {
+      records
+        .iter()
+        .map(|record| record[c].chars().count())
+        .max()
+        .unwrap_or(0)
+    })
+    .collect();
+
+  records
+    .iter()
+    .map(|record| {
+      let line: Vec<String> = record
+        .iter()
+        .zip(&widths)
+        .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+        .collect();
+      line.join("  ").trim_end().to_string()
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn render_long(records: &[Vec<String>]) -> String {
+  let columns = records.first().map_or(0, Vec::len);
+  let layout: Vec<(usize, bool)> = (0..columns)
+    .map(|c| {
+      let width = records
+        .iter()
+        .map(|record| record[c].chars().count())
+        .max()
+        .unwrap_or(0);
+      let numeric = records
+        .iter()
+        .all(|record| record[c].parse::<u64>().is_ok());
+      (width, numeric)
+    })
+    .collect();
+
+  records
+    .iter()
+    .map(|record| {
+      let line: Vec<String> = record
+        .iter()
+        .zip(&layout)
+        .map(|(cell, &(width, numeric))| {
+          if numeric {
+            format!("{:>width$}", cell, width = width)
+          } else {
+            format!("{:<width$}", cell, width = width)
+          }
+        })
+        .collect();
+      line.join(" ").trim_end().to_string()
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Tabled)]
+  struct Row {
+    #[tabled(rename = "Name")]
+    name: &'static str,
+    #[tabled(rename = "Size")]
+    size: u64,
+  }
+
+  fn rows() -> Vec<Row> {
+    vec![
+      Row {
+        name: "Cargo.toml",
+        size: 120,
+      },
+      Row {
+        name: "src",
+        size: 4096,
+      },
+    ]
+  }
+
+  #[test]
+  fn plain_output_aligns_columns() {
+    assert_eq!(
+      Output::Plain.render(&rows()),
+      "Name        Size\nCargo.toml  120\nsrc         4096"
+    );
+  }
+
+  #[test]
+  fn long_output_right_aligns_numbers() {
+    assert_eq!(
+      Output::Long.render(&rows()),
+      "Cargo.toml  120\nsrc        4096"
+    );
+    assert_eq!(Output::Long.render(&rows()[..0]), "");
+  }
+
+  #[test]
+  fn hidden_columns_are_left_out() {
+    assert_eq!(
+      Output::Plain.render_without(&rows(), &["Size"]),
+      "Name\nCargo.toml\nsrc"
+    );
+    let table = Output::Table.render_without(&rows(), &["Size"]);
+    assert!(table.contains("Cargo.toml"));
+    assert!(!table.contains("Size"));
+  }
+
+  #[test]
+  fn table_output_has_a_header_row() {
+    let table = Output::Table.render(&rows());
+    let lines: Vec<&str> = table.lines().collect();
+    assert!(lines[1].contains("Name") && lines[1].contains("Size"));
+    assert!(lines[3].contains("Cargo.toml") && lines[3].contains("120"));
+  }
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::entry::DirEntryInfo;
+
+/// List the entries of a single directory, including hidden ones.
+/// Entries removed while the directory is being read are left out.
+pub fn read_dir(dir: &Path) -> io::Result<Vec<DirEntryInfo>> {
+  let mut entries = Vec::new();
+  for entry in fs::read_dir(dir)? {
+    match DirEntryInfo::from_dir_entry(&entry?) {
+      Ok(info) => entries.push(info),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+      Err(e) => return Err(e),
+    }
+  }
+  Ok(entries)
+}
+
+/// Visit every entry below `dir`, depth first, without following symlinks.
+/// Directories are visited before their contents. A subdirectory that cannot
+/// be read is passed to `skipped` and its contents are left out; only an
+/// unreadable `dir` is an error.
+pub fn walk<F, S>(dir: &Path, visit: &mut F, skipped: &mut S) -> io::Result<()>
+where
+  F: FnMut(&DirEntryInfo) -> io::Result<()>,
+  S: FnMut(&Path, io::Error),
+{
+  visit_entries(read_dir(dir)?, visit, skipped)
+}
+
+fn visit_entries<F, S>(entries: Vec<DirEntryInfo>, visit: &mut F, skipped: &mut S) -> io::Result<()>
+where
+  F: FnMut(&DirEntryInfo) -> io::Result<()>,
+  S: FnMut(&Path, io::Error),
+{
+  for entry in entries {
+    visit(&entry)?;
+
+    if entry.is_dir() {
+      match read_dir(&entry.path) {
+        Ok(children) => visit_entries(children, visit, skipped)?,
+        Err(e) => skipped(&entry.path, e),
+      }
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn walks_nested_directories() {
+    let root = std::env::temp_dir().join(format!("ls_common_walk_{}", std::process::id()));
+    fs::create_dir_all(root.join("a/b")).unwrap();
+    fs::write(root.join("top.txt"), "1").unwrap();
+    fs::write(root.join("a/b/deep.txt"), "2").unwrap();
+    std::os::unix::fs::symlink(&root, root.join("a/loop")).unwrap();
+
+    let mut seen = Vec::new();
+    walk(
+      &root,
+      &mut |entry| {
+        seen.push(entry.path.strip_prefix(&root).unwrap().to_path_buf());
+        Ok(())
+      },
+      &mut |path, e| panic!("{}: {}", path.display(), e),
+    )
+    .unwrap();
+    seen.sort();
+
+    let expected: Vec<_> = ["a", "a/b", "a/b/deep.txt", "a/loop", "top.txt"]
+      .iter()
+      .map(std::path::PathBuf::from)
+      .collect();
+    assert_eq!(seen, expected);
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn skips_unreadable_subdirectories() {
+    let root = std::env::temp_dir().join(format!("ls_common_skip_{}", std::process::id()));
+    fs::create_dir_all(root.join("gone")).unwrap();
+    fs::write(root.join("top.txt"), "1").unwrap();
+
+    let mut seen = Vec::new();
+    let mut skipped = Vec::new();
+    walk(
+      &root,
+      &mut |entry| {
+        // Stands in for a directory without read permission, which root could still read
+        if entry.name == "gone" {
+          fs::remove_dir(&entry.path)?;
+        }
+        seen.push(entry.name.clone());
+        Ok(())
+      },
+      &mut |path, e| skipped.push((path.to_path_buf(), e.kind())),
+    )
+    .unwrap();
+    seen.sort();
+
+    assert_eq!(seen, ["gone", "top.txt"]);
+    assert_eq!(skipped, [(root.join("gone"), io::ErrorKind::NotFound)]);
+    assert!(walk(&root.join("missing"), &mut |_| Ok(()), &mut |_, _| {}).is_err());
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
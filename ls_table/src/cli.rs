@@ -0,0 +1,69 @@
+use clap::{Parser, Subcommand};
+
+use crate::dupes::HashAlgorithm;
+
+/// List a directory as a table, sorted by size.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, name = "ls_table")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Commands>,
+  /// The directory to list
+  #[arg(default_value = ".")]
+  pub path: String,
+  /// Show a column with each entry's git state (modified, staged, untracked, ignored)
+  #[arg(long)]
+  pub git: bool,
+  /// Hide entries ignored by the repository's .gitignore rules
+  #[arg(long)]
+  pub gitignore: bool,
+  /// A TOML file with extra content signatures for the Content column
+  #[arg(long, value_name = "FILE")]
+  pub magic_file: Option<String>,
+  /// Print plain aligned columns instead of a table
+  #[arg(long)]
+  pub plain: bool,
+  /// Keep the table on screen and update it as the directory changes
+  #[arg(long)]
+  pub watch: bool,
+  /// How many recent changes to show below the table in watch mode
+  #[arg(long, value_name = "N", default_value_t = 10, requires = "watch")]
+  pub events: usize,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+  /// Record a directory tree, with content hashes, to a snapshot file
+  Snapshot {
+    /// The directory to record
+    #[arg(default_value = ".")]
+    path: String,
+    /// Where to write the snapshot
+    #[arg(short, long, value_name = "FILE")]
+    output: String,
+  },
+  /// Compare a directory tree against a snapshot file
+  Diff {
+    /// A snapshot written by the snapshot command
+    snapshot: String,
+    /// The directory to compare
+    #[arg(default_value = ".")]
+    path: String,
+  },
+  /// Find files with identical content below a directory
+  Dupes {
+    /// The directory to search
+    #[arg(default_value = ".")]
+    path: String,
+    /// The hash used to confirm that files are identical
+    #[arg(long, value_enum, default_value_t = HashAlgorithm::Blake3)]
+    hash: HashAlgorithm,
+    /// Replace duplicates with hard links to the first file of each set
+    #[arg(long)]
+    link: bool,
+    /// Link without asking for confirmation
+    #[arg(long, requires = "link")]
+    yes: bool,
+  },
+}
@@ -0,0 +1,241 @@
+use ls_common::walk;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::snapshot::hex;
+
+// Files larger than this are compared on their first bytes before being hashed in full
+const PARTIAL_LEN: u64 = 4096;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum HashAlgorithm {
+  Blake3,
+  Sha256,
+}
+
+// Files with identical content
+pub struct DuplicateSet {
+  pub size: u64,
+  pub hash: String,
+  pub paths: Vec<PathBuf>,
+}
+
+#[derive(tabled::Tabled)]
+pub struct DuplicateRow {
+  #[tabled(rename = "Set")]
+  set: usize,
+  #[tabled(rename = "Size (bytes)")]
+  size: u64,
+  #[tabled(rename = "Hash")]
+  hash: String,
+  #[tabled(rename = "Path")]
+  path: String,
+}
+
+impl DuplicateSet {
+  // Space freed by keeping a single copy
+  pub fn reclaimable(&self) -> u64 {
+    self.size * (self.paths.len() as u64 - 1)
+  }
+
+  pub fn to_rows(&self, set: usize) -> Vec<DuplicateRow> {
+    self
+      .paths
+      .iter()
+      .map(|path| DuplicateRow {
+        set,
+        size: self.size,
+        hash: self.hash.chars().take(16).collect(),
+        path: path.display().to_string(),
+      })
+      .collect()
+  }
+
+  // Replace every copy but the first with a hard link to it. Returns the bytes reclaimed.
+  // The files are hashed again first, since they may have changed while the user was
+  // asked, and copies whose mode or owner differ from the kept file are left alone.
+  pub fn link(&self, algorithm: HashAlgorithm) -> io::Result<u64> {
+    let keep = &self.paths[0];
+    if hash_file(keep, algorithm, None)? != self.hash {
+      eprintln!(
+        "Skipping the copies of {}: it changed since it was hashed",
+        keep.display()
+      );
+      return Ok(0);
+    }
+
+    let mut reclaimed = 0;
+    for duplicate in &self.paths[1..] {
+      match self.link_duplicate(keep, duplicate, algorithm) {
+        Ok(()) => reclaimed += self.size,
+        Err(e) => eprintln!("Skipping {}: {}", duplicate.display(), e),
+      }
+    }
+    Ok(reclaimed)
+  }
+
+  fn link_duplicate(
+    &self,
+    keep: &Path,
+    duplicate: &Path,
+    algorithm: HashAlgorithm,
+  ) -> io::Result<()> {
+    let kept = fs::metadata(keep)?;
+    let metadata = fs::metadata(duplicate)?;
+    if metadata.dev() != kept.dev() {
+      return Err(io::Error::other(format!(
+        "not on the same filesystem as {}",
+        keep.display()
+      )));
+    }
+    if (metadata.mode(), metadata.uid(), metadata.gid()) != (kept.mode(), kept.uid(), kept.gid()) {
+      return Err(io::Error::other(format!(
+        "its mode or owner differs from {}",
+        keep.display()
+      )));
+    }
+    if hash_file(duplicate, algorithm, None)? != self.hash {
+      return Err(io::Error::other("it changed since it was hashed"));
+    }
+
+    // Link under a temporary name first so the duplicate is never missing
+    let file_name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = duplicate.with_file_name(format!(".{}.dupes-tmp", file_name));
+    if fs::symlink_metadata(&temporary).is_ok() {
+      return Err(io::Error::other(format!(
+        "{} is left over from an earlier run, remove it first",
+        temporary.display()
+      )));
+    }
+    fs::hard_link(keep, &temporary)?;
+    fs::rename(&temporary, duplicate).inspect_err(|_| {
+      let _ = fs::remove_file(&temporary);
+    })
+  }
+}
+
+// Find sets of identical files below `root`, largest reclaimable space first.
+// Candidates are narrowed by size, then by a hash of their first bytes, then by a full hash.
+pub fn find_duplicates(root: &Path, algorithm: HashAlgorithm) -> io::Result<Vec<DuplicateSet>> {
+  // Group regular files by size, counting existing hard links only once
+  let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+  let mut inodes = HashSet::new();
+  walk(
+    root,
+    &mut |entry| {
+      let regular = entry.is_file() && entry.size > 0;
+      if regular && inodes.insert((entry.dev, entry.ino)) {
+        by_size
+          .entry(entry.size)
+          .or_default()
+          .push(entry.path.clone());
+      }
+      Ok(())
+    },
+    &mut |path, e| eprintln!("Skipping {}: {}", path.display(), e),
+  )?;
+
+  let mut sets = Vec::new();
+  for (size, paths) in by_size {
+    if paths.len() < 2 {
+      continue;
+    }
+
+    for candidates in group_by_hash(&paths, algorithm, Some(PARTIAL_LEN)) {
+      // Small files were read completely by the partial hash
+      let groups = if size <= PARTIAL_LEN {
+        vec![candidates]
+      } else {
+        group_by_hash(&candidates.1, algorithm, None)
+      };
+
+      sets.extend(
+        groups
+          .into_iter()
+          .map(|(hash, paths)| DuplicateSet { size, hash, paths }),
+      );
+    }
+  }
+
+  sets.sort_by_key(|set| std::cmp::Reverse(set.reclaimable()));
+  Ok(sets)
+}
+
+// Hash each file, or only its first `limit` bytes, and keep the groups with several members
+fn group_by_hash(
+  paths: &[PathBuf],
+  algorithm: HashAlgorithm,
+  limit: Option<u64>,
+) -> Vec<(String, Vec<PathBuf>)> {
+  let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+  for path in paths {
+    match hash_file(path, algorithm, limit) {
+      Ok(hash) => groups.entry(hash).or_default().push(path.clone()),
+      Err(e) => eprintln!("Skipping {}: {}", path.display(), e),
+    }
+  }
+
+  groups
+    .into_iter()
+    .filter(|(_, paths)| paths.len() > 1)
+    .map(|(hash, mut paths)| {
+      paths.sort();
+      (hash, paths)
+    })
+    .collect()
+}
+
+fn hash_file(path: &Path, algorithm: HashAlgorithm, limit: Option<u64>) -> io::Result<String> {
+  let mut reader = File::open(path)?.take(limit.unwrap_or(u64::MAX));
+  match algorithm {
+    HashAlgorithm::Blake3 => {
+      let mut hasher = blake3::Hasher::new();
+      io::copy(&mut reader, &mut hasher)?;
+      Ok(hasher.finalize().to_hex().to_string())
+    }
+    HashAlgorithm::Sha256 => {
+      let mut hasher = Sha256::new();
+      io::copy(&mut reader, &mut hasher)?;
+      Ok(hex(&hasher.finalize()))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::os::unix::fs::PermissionsExt;
+
+  #[test]
+  fn links_only_unchanged_copies_with_the_same_mode() {
+    let root = std::env::temp_dir().join(format!("ls_table_dupes_{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    for name in ["a", "b", "c", "d", "e"] {
+      fs::write(root.join(name), "same content").unwrap();
+    }
+
+    let sets = find_duplicates(&root, HashAlgorithm::Blake3).unwrap();
+    assert_eq!(sets.len(), 1);
+    assert_eq!(sets[0].paths.len(), 5);
+    assert_eq!(sets[0].reclaimable(), 4 * 12);
+
+    // Changes made while the user is being asked
+    fs::write(root.join("b"), "other content").unwrap();
+    fs::set_permissions(root.join("c"), fs::Permissions::from_mode(0o600)).unwrap();
+    fs::write(root.join(".d.dupes-tmp"), "").unwrap();
+
+    assert_eq!(sets[0].link(HashAlgorithm::Blake3).unwrap(), 12);
+    let inode = |name: &str| fs::metadata(root.join(name)).unwrap().ino();
+    assert_eq!(inode("e"), inode("a"));
+    for name in ["b", "c", "d"] {
+      assert_ne!(inode(name), inode("a"), "{}", name);
+    }
+    assert_eq!(fs::read_to_string(root.join("b")).unwrap(), "other content");
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
@@ -0,0 +1,177 @@
+use git2::{Repository, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+// Status of every interesting path in the repository, read from the index and working tree
+pub struct GitStatus {
+  repo: Repository,
+  // The listed directory, relative to the repository's working tree
+  prefix: PathBuf,
+  entries: Vec<(String, Status)>,
+}
+
+impl GitStatus {
+  // Open the repository containing `dir`, or return None if it is not inside a working tree
+  pub fn open(dir: &Path) -> Option<GitStatus> {
+    let repo = Repository::discover(dir).ok()?;
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    let prefix = dir
+      .canonicalize()
+      .ok()?
+      .strip_prefix(&workdir)
+      .ok()?
+      .to_path_buf();
+
+    let mut options = StatusOptions::new();
+    options
+      .include_untracked(true)
+      .include_ignored(true)
+      .recurse_untracked_dirs(false)
+      .recurse_ignored_dirs(false);
+
+    let entries = repo
+      .statuses(Some(&mut options))
+      .ok()?
+      .iter()
+      .filter_map(|entry| Some((entry.path().ok()?.to_string(), entry.status())))
+      .collect();
+
+    Some(GitStatus {
+      repo,
+      prefix,
+      entries,
+    })
+  }
+
+  // Combined status of an entry of the listed directory.
+  // A directory takes on the state of everything below it, except for ignored children.
+  pub fn status_of(&self, name: &str) -> Status {
+    let path = self.relative(name);
+    let dir_prefix = format!("{}/", path);
+
+    let mut status = Status::empty();
+    for (entry_path, entry_status) in &self.entries {
+      if *entry_path == path || *entry_path == dir_prefix {
+        status |= *entry_status;
+      } else if entry_path.starts_with(&dir_prefix) {
+        status |= *entry_status - Status::IGNORED;
+      }
+    }
+    status
+  }
+
+  // Check whether an entry of the listed directory is excluded by .gitignore
+  pub fn is_ignored(&self, name: &str) -> bool {
+    self
+      .repo
+      .is_path_ignored(self.prefix.join(name))
+      .unwrap_or(false)
+  }
+
+  fn relative(&self, name: &str) -> String {
+    self.prefix.join(name).to_string_lossy().into_owned()
+  }
+}
+
+// Describe a status the way it is shown in the Git column (e.g. "staged, modified")
+pub fn describe(status: Status) -> String {
+  let labels = [
+    (Status::CONFLICTED, "conflicted"),
+    (
+      Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE,
+      "staged",
+    ),
+    (
+      Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+      "modified",
+    ),
+    (Status::WT_NEW, "untracked"),
+    (Status::IGNORED, "ignored"),
+  ];
+
+  let matched: Vec<&str> = labels
+    .iter()
+    .filter(|(flags, _)| status.intersects(*flags))
+    .map(|(_, label)| *label)
+    .collect();
+
+  if matched.is_empty() {
+    "clean".to_string()
+  } else {
+    matched.join(", ")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn describes_each_state() {
+    assert_eq!(describe(Status::empty()), "clean");
+    assert_eq!(describe(Status::CURRENT), "clean");
+    assert_eq!(describe(Status::INDEX_NEW), "staged");
+    assert_eq!(describe(Status::WT_DELETED), "modified");
+    assert_eq!(describe(Status::WT_NEW), "untracked");
+    assert_eq!(describe(Status::IGNORED), "ignored");
+    assert_eq!(describe(Status::CONFLICTED), "conflicted");
+  }
+
+  #[test]
+  fn combines_states_in_a_fixed_order() {
+    assert_eq!(
+      describe(Status::WT_MODIFIED | Status::INDEX_MODIFIED),
+      "staged, modified"
+    );
+    assert_eq!(
+      describe(Status::IGNORED | Status::WT_NEW | Status::CONFLICTED),
+      "conflicted, untracked, ignored"
+    );
+  }
+
+  #[test]
+  fn maps_a_working_tree() {
+    let root = std::env::temp_dir().join(format!("ls_table_git_{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    let repo = Repository::init(&root).unwrap();
+    fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+    fs::write(root.join("committed.txt"), "1").unwrap();
+    fs::write(root.join("src/lib.rs"), "1").unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("committed.txt")).unwrap();
+    index.add_path(Path::new("src/lib.rs")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    repo
+      .commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+      .unwrap();
+
+    fs::write(root.join("src/lib.rs"), "2").unwrap();
+    fs::write(root.join("new.txt"), "1").unwrap();
+    fs::write(root.join("debug.log"), "1").unwrap();
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::write(root.join("target/out"), "1").unwrap();
+
+    let status = GitStatus::open(&root).unwrap();
+    let described = |name: &str| describe(status.status_of(name));
+    assert_eq!(described("committed.txt"), "clean");
+    assert_eq!(described("src"), "modified");
+    assert_eq!(described("new.txt"), "untracked");
+    assert_eq!(described("debug.log"), "ignored");
+    assert_eq!(described("target"), "ignored");
+    assert!(status.is_ignored("debug.log"));
+    assert!(!status.is_ignored("new.txt"));
+
+    // Opened from a subdirectory, names are relative to it
+    let status = GitStatus::open(&root.join("src")).unwrap();
+    assert_eq!(describe(status.status_of("lib.rs")), "modified");
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
@@ -0,0 +1,289 @@
+use ls_common::{DirEntryInfo, EntryKind};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+// How many bytes are read from each file when no signature needs more
+const MIN_SNIFF_LEN: usize = 512;
+
+// Signatures further into a file than this are rejected, which keeps the
+// head read from every file small
+const MAX_OFFSET: usize = 64 * 1024;
+
+// A byte sequence that identifies a file format when found at a fixed offset
+#[derive(Deserialize, Debug)]
+pub struct Signature {
+  pub mime: String,
+  #[serde(default)]
+  pub offset: usize,
+  #[serde(deserialize_with = "hex_bytes")]
+  pub bytes: Vec<u8>,
+}
+
+// Layout of a user signature file:
+//
+//   [[signature]]
+//   mime = "application/vnd.sqlite3"
+//   bytes = "53 51 4c 69 74 65"
+#[derive(Deserialize)]
+struct SignatureFile {
+  #[serde(default)]
+  signature: Vec<Signature>,
+}
+
+// The signature database used to fill the Content column
+pub struct MagicDb {
+  signatures: Vec<Signature>,
+}
+
+impl MagicDb {
+  // The formats recognised out of the box
+  pub fn builtin() -> MagicDb {
+    let builtin: &[(&str, usize, &[u8])] = &[
+      ("application/x-elf", 0, b"\x7fELF"),
+      ("image/png", 0, b"\x89PNG\r\n\x1a\n"),
+      ("image/jpeg", 0, b"\xff\xd8\xff"),
+      ("image/gif", 0, b"GIF8"),
+      ("application/gzip", 0, b"\x1f\x8b"),
+      ("application/x-bzip2", 0, b"BZh"),
+      ("application/x-xz", 0, b"\xfd7zXZ\x00"),
+      ("application/zstd", 0, b"\x28\xb5\x2f\xfd"),
+      ("application/zip", 0, b"PK\x03\x04"),
+      ("application/zip", 0, b"PK\x05\x06"),
+      ("application/x-tar", 257, b"ustar"),
+      ("application/pdf", 0, b"%PDF-"),
+      ("application/wasm", 0, b"\x00asm"),
+    ];
+
+    MagicDb {
+      signatures: builtin
+        .iter()
+        .map(|&(mime, offset, bytes)| Signature {
+          mime: mime.to_string(),
+          offset,
+          bytes: bytes.to_vec(),
+        })
+        .collect(),
+    }
+  }
+
+  // Add the signatures from a TOML file. They take precedence over the built-in ones.
+  pub fn extend_from_file(&mut self, path: &Path) -> io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    let file: SignatureFile = toml::from_str(&content).map_err(|e| {
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), e),
+      )
+    })?;
+    if let Some(sig) = file.signature.iter().find(|sig| sig.offset > MAX_OFFSET) {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+          "{}: offset {} of {} is larger than {}",
+          path.display(),
+          sig.offset,
+          sig.mime,
+          MAX_OFFSET
+        ),
+      ));
+    }
+    self.signatures.splice(0..0, file.signature);
+    Ok(())
+  }
+
+  // Detect the content type of a directory entry
+  pub fn detect(&self, entry: &DirEntryInfo) -> String {
+    let special = match entry.kind {
+      EntryKind::Dir => Some("inode/directory"),
+      EntryKind::Symlink => Some("inode/symlink"),
+      EntryKind::Fifo => Some("inode/fifo"),
+      EntryKind::Socket => Some("inode/socket"),
+      EntryKind::CharDevice => Some("inode/chardevice"),
+      EntryKind::BlockDevice => Some("inode/blockdevice"),
+      EntryKind::File if entry.size == 0 => Some("inode/x-empty"),
+      EntryKind::File => None,
+    };
+    if let Some(mime) = special {
+      return mime.to_string();
+    }
+
+    match self.read_head(&entry.path) {
+      Ok(head) => self.sniff(&head),
+      Err(_) => "unreadable".to_string(),
+    }
+  }
+
+  // Classify the first bytes of a regular file
+  pub fn sniff(&self, head: &[u8]) -> String {
+    let matched = self.signatures.iter().find(|sig| {
+      head
+        .get(sig.offset..sig.offset + sig.bytes.len())
+        .is_some_and(|window| window == sig.bytes.as_slice())
+    });
+    if let Some(sig) = matched {
+      return sig.mime.clone();
+    }
+
+    if let Some(script) = head.strip_prefix(b"#!") {
+      return script_type(script);
+    }
+
+    if is_text(head) {
+      "text/plain".to_string()
+    } else {
+      "application/octet-stream".to_string()
+    }
+  }
+
+  fn read_head(&self, path: &Path) -> io::Result<Vec<u8>> {
+    let len = self
+      .signatures
+      .iter()
+      .map(|sig| sig.offset + sig.bytes.len())
+      .max()
+      .unwrap_or(0)
+      .max(MIN_SNIFF_LEN);
+
+    let mut head = Vec::with_capacity(len);
+    File::open(path)?.take(len as u64).read_to_end(&mut head)?;
+    Ok(head)
+  }
+}
+
+// Name a script after its interpreter, e.g. "#!/usr/bin/env python3" -> text/x-script.python3
+fn script_type(shebang: &[u8]) -> String {
+  let line = shebang.split(|&b| b == b'\n').next().unwrap_or_default();
+  let line = String::from_utf8_lossy(line);
+  let mut words = line.split_whitespace();
+
+  let mut interpreter = words
+    .next()
+    .and_then(|w| w.rsplit('/').next())
+    .unwrap_or("");
+  if interpreter == "env" {
+    interpreter = words.find(|w| !w.starts_with('-')).unwrap_or("");
+  }
+
+  match interpreter {
+    "sh" | "bash" | "dash" | "zsh" | "ksh" => "text/x-shellscript".to_string(),
+    "" => "text/x-script".to_string(),
+    name => format!("text/x-script.{}", name),
+  }
+}
+
+// Text is valid UTF-8 without NUL bytes; a multi-byte character cut off by the read is allowed
+fn is_text(head: &[u8]) -> bool {
+  if head.contains(&0) {
+    return false;
+  }
+  match std::str::from_utf8(head) {
+    Ok(_) => true,
+    Err(e) => e.error_len().is_none(),
+  }
+}
+
+// Parse a hex string such as "7f 45 4c 46" or "7f454c46"
+fn hex_bytes<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  let text = String::deserialize(deserializer)?;
+  let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+  if digits.is_empty() || !digits.len().is_multiple_of(2) {
+    return Err(serde::de::Error::custom(
+      "expected an even number of hex digits",
+    ));
+  }
+
+  digits
+    .chunks(2)
+    .map(|pair| {
+      std::str::from_utf8(pair)
+        .ok()
+        .and_then(|s| u8::from_str_radix(s, 16).ok())
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid hex byte in {:?}", text)))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sniffs_builtin_signatures() {
+    let magic = MagicDb::builtin();
+    assert_eq!(magic.sniff(b"\x7fELF\x02\x01\x01"), "application/x-elf");
+    assert_eq!(magic.sniff(b"\x89PNG\r\n\x1a\n\0\0"), "image/png");
+    assert_eq!(magic.sniff(b"PK\x03\x04rest"), "application/zip");
+    assert_eq!(magic.sniff(b"%PDF-1.7\n"), "application/pdf");
+
+    let mut tar = vec![0; 512];
+    tar[257..262].copy_from_slice(b"ustar");
+    assert_eq!(magic.sniff(&tar), "application/x-tar");
+    // Too short to reach the tar signature's offset
+    assert_eq!(magic.sniff(&tar[..200]), "application/octet-stream");
+  }
+
+  #[test]
+  fn user_signatures_come_first() {
+    let path = std::env::temp_dir().join(format!("ls_table_magic_{}.toml", std::process::id()));
+    fs::write(
+      &path,
+      "[[signature]]\nmime = \"application/x-custom-zip\"\nbytes = \"50 4b 03 04\"\n",
+    )
+    .unwrap();
+    let mut magic = MagicDb::builtin();
+    magic.extend_from_file(&path).unwrap();
+    assert_eq!(magic.sniff(b"PK\x03\x04"), "application/x-custom-zip");
+
+    for signature in [
+      "bytes = \"7f4\"",
+      "bytes = \"zz\"",
+      "bytes = \"\"",
+      "bytes = \"00\"\noffset = 9223372036854775807",
+    ] {
+      let content = format!("[[signature]]\nmime = \"x\"\n{}\n", signature);
+      fs::write(&path, content).unwrap();
+      let error = magic.extend_from_file(&path).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn names_scripts_after_their_interpreter() {
+    assert_eq!(script_type(b"/bin/sh\necho"), "text/x-shellscript");
+    assert_eq!(script_type(b"/usr/bin/bash -e"), "text/x-shellscript");
+    assert_eq!(
+      script_type(b"/usr/bin/env python3\n"),
+      "text/x-script.python3"
+    );
+    assert_eq!(
+      script_type(b"/usr/bin/env -S perl -w"),
+      "text/x-script.perl"
+    );
+    assert_eq!(script_type(b"\n"), "text/x-script");
+    assert_eq!(
+      MagicDb::builtin().sniff(b"#!/usr/bin/env node\n"),
+      "text/x-script.node"
+    );
+  }
+
+  #[test]
+  fn tells_text_from_binary() {
+    assert!(is_text(b"plain ascii\n"));
+    assert!(is_text("caf\u{e9}".as_bytes()));
+    // A two-byte character cut off after its first byte
+    assert!(is_text(b"caf\xc3"));
+    assert!(!is_text(b"caf\xc3x"));
+    assert!(!is_text(b"nul\0inside"));
+    assert_eq!(
+      MagicDb::builtin().sniff(b"\x01\x02\xff"),
+      "application/octet-stream"
+    );
+    assert_eq!(MagicDb::builtin().sniff(b"hello\n"), "text/plain");
+  }
+}
@@ -1,79 +1,207 @@
-use chrono::{DateTime, Local};
-use std::fs;
-use std::os::unix::fs::PermissionsExt;
-use tabled::{
-  Table,
-  settings::{Alignment, Modify, Style, object::Rows},
-};
+use clap::Parser;
+use ls_common::{DirEntryInfo, Output, format_permissions, format_time};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::Path;
+
+mod cli;
+mod dupes;
+mod git;
+mod magic;
+mod snapshot;
+mod watch;
+
+use cli::{Cli, Commands};
+use dupes::HashAlgorithm;
+use git::GitStatus;
+use magic::MagicDb;
+use snapshot::Snapshot;
 
 fn main() -> std::io::Result<()> {
-  // Read current directory
-  let entries = fs::read_dir(".")?;
+  let cli = Cli::parse();
+
+  match &cli.command {
+    Some(Commands::Snapshot { path, output }) => {
+      let snapshot = Snapshot::take(Path::new(path), &MagicDb::builtin())?;
+      snapshot.save(Path::new(output))?;
+      println!(
+        "Recorded {} entries of {} to {}",
+        snapshot.entries.len(),
+        snapshot.root,
+        output
+      );
+      Ok(())
+    }
+    Some(Commands::Diff { snapshot, path }) => diff(Path::new(snapshot), Path::new(path)),
+    Some(Commands::Dupes {
+      path,
+      hash,
+      link,
+      yes,
+    }) => find_dupes(Path::new(path), *hash, *link, *yes),
+    None => list(&cli),
+  }
+}
+
+// Print the table for a single directory, or keep it updated with --watch
+fn list(cli: &Cli) -> std::io::Result<()> {
+  // Load the content signatures, including the user's own
+  let mut magic = MagicDb::builtin();
+  if let Some(magic_file) = &cli.magic_file {
+    magic.extend_from_file(Path::new(magic_file))?;
+  }
+
+  if cli.watch {
+    return watch::run(cli, &magic);
+  }
+
+  println!("{}", render_listing(cli, &magic)?);
+  Ok(())
+}
+
+// Build the table for the directory given on the command line
+fn render_listing(cli: &Cli, magic: &MagicDb) -> std::io::Result<String> {
+  // Read the requested directory
+  let entries = ls_common::read_dir(Path::new(&cli.path))?;
+
+  // Look up the enclosing git repository only when it is needed
+  let git_status = if cli.git || cli.gitignore {
+    GitStatus::open(Path::new(&cli.path))
+  } else {
+    None
+  };
 
   // Create a vector to store file information
   let mut files: Vec<FileInfo> = Vec::new();
 
   // Collect file information, including hidden files
   for entry in entries {
-    let entry = entry?;
-    let metadata = entry.metadata()?;
+    let name = entry.name.clone();
 
-    let name = entry.file_name().into_string().unwrap_or_default();
-    let size = metadata.len();
-    let modified: DateTime<Local> = metadata.modified()?.into();
-    let permissions = metadata.permissions();
-    let perms = format_permissions(permissions.mode());
-    let file_type = if metadata.is_dir() { "dir" } else { "file" };
+    // Skip ignored entries when asked to
+    if cli.gitignore && git_status.as_ref().is_some_and(|g| g.is_ignored(&name)) {
+      continue;
+    }
 
-    files.push(FileInfo {
-      name,
-      size,
-      modified: modified.format("%Y-%m-%d %H:%M:%S").to_string(),
-      permissions: perms,
-      file_type: file_type.to_string(),
-    });
+    let mut info = FileInfo::new(name, &entry, magic);
+    if let Some(g) = &git_status {
+      info.git = git::describe(g.status_of(&info.name));
+    }
+    files.push(info);
   }
 
   // Sort files by size (descending)
-  files.sort_by(|a, b| b.size.cmp(&a.size));
+  files.sort_by_key(|f| std::cmp::Reverse(f.size));
+
+  // The git column is optional
+  let hidden: &[&str] = if cli.git { &[] } else { &["Git"] };
+  let output = if cli.plain {
+    Output::Plain
+  } else {
+    Output::Table
+  };
+  Ok(output.render_without(&files, hidden))
+}
+
+// Compare the live tree with a snapshot and exit with status 1 if it has drifted
+fn diff(snapshot_path: &Path, path: &Path) -> std::io::Result<()> {
+  let recorded = Snapshot::load(snapshot_path)?;
+  let current = Snapshot::take(path, &MagicDb::builtin())?;
+  let changes = recorded.diff(&current);
+
+  if changes.is_empty() {
+    println!("No changes since {} ({})", recorded.taken, recorded.root);
+    return Ok(());
+  }
+
+  let rows: Vec<_> = changes.iter().map(|change| change.to_row()).collect();
+  println!("{}", Output::Table.render(&rows));
+  println!("{} changes since {}", changes.len(), recorded.taken);
+  std::process::exit(1);
+}
+
+// Report duplicate files and optionally hard link them together
+fn find_dupes(path: &Path, hash: HashAlgorithm, link: bool, yes: bool) -> io::Result<()> {
+  let sets = dupes::find_duplicates(path, hash)?;
+  if sets.is_empty() {
+    println!("No duplicate files found");
+    return Ok(());
+  }
 
-  // Create table with a different style
-  let table = Table::new(files)
-    .with(Style::modern())
-    .with(Modify::new(Rows::new(1..)).with(Alignment::left()))
-    .to_string();
+  let rows: Vec<_> = sets
+    .iter()
+    .enumerate()
+    .flat_map(|(index, set)| set.to_rows(index + 1))
+    .collect();
+  println!("{}", Output::Table.render(&rows));
 
-  println!("{}", table);
+  let duplicates: usize = sets.iter().map(|set| set.paths.len() - 1).sum();
+  let reclaimable: u64 = sets.iter().map(|set| set.reclaimable()).sum();
+  println!(
+    "{} duplicate sets, {} redundant files, {} bytes reclaimable",
+    sets.len(),
+    duplicates,
+    reclaimable
+  );
 
+  if !link {
+    return Ok(());
+  }
+  if !yes {
+    print!("Replace {} files with hard links? [y/N] ", duplicates);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+      println!("Nothing changed");
+      return Ok(());
+    }
+  }
+
+  let mut reclaimed = 0;
+  for set in &sets {
+    reclaimed += set.link(hash)?;
+  }
+  println!("Reclaimed {} bytes", reclaimed);
   Ok(())
 }
 
-// Struct to hold file information
-#[derive(tabled::Tabled)]
+// Struct to hold file information, also the record kept for each entry of a snapshot
+#[derive(tabled::Tabled, Serialize, Deserialize, Clone, PartialEq, Debug)]
 struct FileInfo {
   #[tabled(rename = "Name")]
   name: String,
   #[tabled(rename = "Type")]
   file_type: String,
+  #[tabled(rename = "Content")]
+  content: String,
   #[tabled(rename = "Size (bytes)")]
   size: u64,
   #[tabled(rename = "Modified")]
   modified: String,
   #[tabled(rename = "Permissions")]
   permissions: String,
+  #[tabled(rename = "Git")]
+  #[serde(skip)]
+  git: String,
+  // File content hash, or the hash of the target for symlinks; only snapshots fill it in
+  #[tabled(skip)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  hash: Option<String>,
 }
 
-// Format permissions in Unix style (e.g., -rwxr-xr-x)
-fn format_permissions(mode: u32) -> String {
-  let user = triplet(mode, 6);
-  let group = triplet(mode, 3);
-  let other = triplet(mode, 0);
-  format!("{}{}{}", user, group, other)
-}
-
-fn triplet(mode: u32, shift: u32) -> String {
-  let r = if (mode >> shift) & 0x4 != 0 { "r" } else { "-" };
-  let w = if (mode >> shift) & 0x2 != 0 { "w" } else { "-" };
-  let x = if (mode >> shift) & 0x1 != 0 { "x" } else { "-" };
-  format!("{}{}{}", r, w, x)
+impl FileInfo {
+  // The columns every listing shows, for an entry displayed as `name`
+  fn new(name: String, entry: &DirEntryInfo, magic: &MagicDb) -> FileInfo {
+    FileInfo {
+      name,
+      file_type: entry.kind.label().to_string(),
+      content: magic.detect(entry),
+      size: entry.size,
+      modified: format_time(entry.modified),
+      permissions: format_permissions(entry.mode),
+      git: String::new(),
+      hash: None,
+    }
+  }
 }
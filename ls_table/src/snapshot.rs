@@ -0,0 +1,341 @@
+use ls_common::{DirEntryInfo, EntryKind, format_time, walk};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::FileInfo;
+use crate::magic::MagicDb;
+
+// A recorded directory tree, one listing row per entry with paths relative to the root
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+  pub root: String,
+  pub taken: String,
+  // Sorted by path
+  pub entries: Vec<FileInfo>,
+}
+
+// One difference between a snapshot and the live tree
+#[derive(Debug, PartialEq)]
+pub enum Change {
+  Added(String),
+  Removed(String),
+  Modified(String),
+  PermissionsChanged {
+    path: String,
+    old: String,
+    new: String,
+  },
+  Moved {
+    from: String,
+    to: String,
+  },
+}
+
+#[derive(tabled::Tabled)]
+pub struct ChangeRow {
+  #[tabled(rename = "Change")]
+  kind: &'static str,
+  #[tabled(rename = "Path")]
+  path: String,
+  #[tabled(rename = "Details")]
+  details: String,
+}
+
+impl Snapshot {
+  // Walk `root` recursively and hash every file
+  pub fn take(root: &Path, magic: &MagicDb) -> io::Result<Snapshot> {
+    let mut entries = Vec::new();
+    walk(
+      root,
+      &mut |entry| {
+        let relative = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+        let mut info = FileInfo::new(relative.to_string_lossy().into_owned(), entry, magic);
+        info.hash = hash_entry(entry)?;
+        entries.push(info);
+        Ok(())
+      },
+      &mut |path, e| eprintln!("Skipping {}: {}", path.display(), e),
+    )?;
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Snapshot {
+      root: root.canonicalize()?.to_string_lossy().into_owned(),
+      taken: format_time(SystemTime::now()),
+      entries,
+    })
+  }
+
+  pub fn load(path: &Path) -> io::Result<Snapshot> {
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader).map_err(io::Error::from)
+  }
+
+  pub fn save(&self, path: &Path) -> io::Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, self).map_err(io::Error::from)
+  }
+
+  // Everything that changed going from `self` to `current`
+  pub fn diff(&self, current: &Snapshot) -> Vec<Change> {
+    let old_entries = by_path(&self.entries);
+    let new_entries = by_path(&current.entries);
+    let mut added: Vec<&str> = Vec::new();
+    let mut removed: Vec<&str> = Vec::new();
+    let mut changes = Vec::new();
+
+    for (&path, old) in &old_entries {
+      match new_entries.get(path) {
+        None => removed.push(path),
+        Some(new) => {
+          if old.file_type != new.file_type || old.hash != new.hash {
+            changes.push(Change::Modified(path.to_string()));
+          }
+          if old.permissions != new.permissions {
+            changes.push(Change::PermissionsChanged {
+              path: path.to_string(),
+              old: old.permissions.clone(),
+              new: new.permissions.clone(),
+            });
+          }
+        }
+      }
+    }
+    for &path in new_entries.keys() {
+      if !old_entries.contains_key(path) {
+        added.push(path);
+      }
+    }
+
+    // A removed file whose content reappears under a new name was moved. When
+    // several files share that content, only the ones that kept their file name
+    // can be told apart; the others are reported as removed and added.
+    let mut moved = Vec::new();
+    let hashes: BTreeSet<&str> = removed
+      .iter()
+      .filter_map(|path| old_entries[path].hash.as_deref())
+      .collect();
+    for hash in hashes {
+      let from: Vec<&str> = removed
+        .iter()
+        .copied()
+        .filter(|path| old_entries[path].hash.as_deref() == Some(hash))
+        .collect();
+      let to: Vec<&str> = added
+        .iter()
+        .copied()
+        .filter(|path| new_entries[path].hash.as_deref() == Some(hash))
+        .collect();
+
+      let pairs: Vec<(&str, &str)> = if from.len() == 1 && to.len() == 1 {
+        vec![(from[0], to[0])]
+      } else {
+        from
+          .iter()
+          .filter_map(|&source| {
+            let name = file_name(source);
+            let sources = from.iter().filter(|path| file_name(path) == name);
+            let mut targets = to.iter().filter(|path| file_name(path) == name);
+            match (sources.count(), targets.next(), targets.next()) {
+              (1, Some(&target), None) => Some((source, target)),
+              _ => None,
+            }
+          })
+          .collect()
+      };
+      for &(source, target) in &pairs {
+        removed.retain(|path| *path != source);
+        added.retain(|path| *path != target);
+      }
+      moved.extend(pairs);
+    }
+    moved.sort();
+
+    changes.extend(moved.into_iter().map(|(from, to)| Change::Moved {
+      from: from.to_string(),
+      to: to.to_string(),
+    }));
+    changes.extend(
+      added
+        .into_iter()
+        .map(|path| Change::Added(path.to_string())),
+    );
+    changes.extend(
+      removed
+        .into_iter()
+        .map(|path| Change::Removed(path.to_string())),
+    );
+    changes
+  }
+}
+
+fn by_path(entries: &[FileInfo]) -> BTreeMap<&str, &FileInfo> {
+  entries
+    .iter()
+    .map(|entry| (entry.name.as_str(), entry))
+    .collect()
+}
+
+fn file_name(path: &str) -> Option<&OsStr> {
+  Path::new(path).file_name()
+}
+
+impl Change {
+  pub fn to_row(&self) -> ChangeRow {
+    let (kind, path, details) = match self {
+      Change::Added(path) => ("added", path.clone(), String::new()),
+      Change::Removed(path) => ("removed", path.clone(), String::new()),
+      Change::Modified(path) => ("modified", path.clone(), String::new()),
+      Change::PermissionsChanged { path, old, new } => {
+        ("permissions", path.clone(), format!("{} -> {}", old, new))
+      }
+      Change::Moved { from, to } => ("moved", to.clone(), format!("from {}", from)),
+    };
+    ChangeRow {
+      kind,
+      path,
+      details,
+    }
+  }
+}
+
+// The content hash of a file, or of the target for symlinks; None for other entries
+fn hash_entry(entry: &DirEntryInfo) -> io::Result<Option<String>> {
+  Ok(match entry.kind {
+    EntryKind::Symlink => {
+      let target = fs::read_link(&entry.path)?;
+      Some(hex(&Sha256::digest(target.as_os_str().as_encoded_bytes())))
+    }
+    EntryKind::File => Some(hash_file(&entry.path)?),
+    _ => None,
+  })
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+  let mut hasher = Sha256::new();
+  io::copy(&mut File::open(path)?, &mut hasher)?;
+  Ok(hex(&hasher.finalize()))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn file(path: &str, content: &str, permissions: &str) -> FileInfo {
+    FileInfo {
+      name: path.to_string(),
+      file_type: "file".to_string(),
+      content: "text/plain".to_string(),
+      size: content.len() as u64,
+      modified: "2024-01-01 00:00:00".to_string(),
+      permissions: permissions.to_string(),
+      git: String::new(),
+      hash: Some(hex(&Sha256::digest(content))),
+    }
+  }
+
+  fn snapshot(entries: Vec<FileInfo>) -> Snapshot {
+    Snapshot {
+      root: "/srv/app".to_string(),
+      taken: "2024-01-01 00:00:00".to_string(),
+      entries,
+    }
+  }
+
+  fn moved(from: &str, to: &str) -> Change {
+    Change::Moved {
+      from: from.to_string(),
+      to: to.to_string(),
+    }
+  }
+
+  #[test]
+  fn reports_each_kind_of_change() {
+    let old = snapshot(vec![
+      file("config.toml", "a = 1", "rw-r--r--"),
+      file("gone.txt", "gone", "rw-r--r--"),
+      file("run.sh", "echo", "rw-r--r--"),
+      file("same.txt", "same", "rw-r--r--"),
+    ]);
+    let new = snapshot(vec![
+      file("config.toml", "a = 2", "rw-r--r--"),
+      file("new.txt", "new", "rw-r--r--"),
+      file("run.sh", "echo", "rwxr-xr-x"),
+      file("same.txt", "same", "rw-r--r--"),
+    ]);
+    assert_eq!(
+      old.diff(&new),
+      [
+        Change::Modified("config.toml".to_string()),
+        Change::PermissionsChanged {
+          path: "run.sh".to_string(),
+          old: "rw-r--r--".to_string(),
+          new: "rwxr-xr-x".to_string(),
+        },
+        Change::Added("new.txt".to_string()),
+        Change::Removed("gone.txt".to_string()),
+      ]
+    );
+    assert!(old.diff(&old).is_empty());
+  }
+
+  #[test]
+  fn finds_a_moved_file() {
+    let old = snapshot(vec![file("a/report.pdf", "pdf", "rw-r--r--")]);
+    let new = snapshot(vec![file("b/final.pdf", "pdf", "rw-r--r--")]);
+    assert_eq!(old.diff(&new), [moved("a/report.pdf", "b/final.pdf")]);
+  }
+
+  #[test]
+  fn pairs_identical_files_by_name() {
+    // Three empty files share a hash; only the names tell them apart
+    let old = snapshot(vec![
+      file("old/a", "", "rw-r--r--"),
+      file("old/b", "", "rw-r--r--"),
+      file("old/c", "", "rw-r--r--"),
+    ]);
+    let new = snapshot(vec![
+      file("new/b", "", "rw-r--r--"),
+      file("new/a", "", "rw-r--r--"),
+      file("new/d", "", "rw-r--r--"),
+    ]);
+    assert_eq!(
+      old.diff(&new),
+      [
+        moved("old/a", "new/a"),
+        moved("old/b", "new/b"),
+        Change::Added("new/d".to_string()),
+        Change::Removed("old/c".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn does_not_guess_between_identical_files() {
+    let old = snapshot(vec![
+      file("x/keep", "", "rw-r--r--"),
+      file("y/keep", "", "rw-r--r--"),
+    ]);
+    let new = snapshot(vec![
+      file("u/keep", "", "rw-r--r--"),
+      file("v/keep", "", "rw-r--r--"),
+    ]);
+    assert_eq!(
+      old.diff(&new),
+      [
+        Change::Added("u/keep".to_string()),
+        Change::Added("v/keep".to_string()),
+        Change::Removed("x/keep".to_string()),
+        Change::Removed("y/keep".to_string()),
+      ]
+    );
+  }
+}
@@ -0,0 +1,260 @@
+use chrono::Local;
+use inotify::{Event, EventMask, Inotify, WatchMask};
+use ls_common::Output;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli::Cli;
+use crate::magic::MagicDb;
+use crate::render_listing;
+
+// Clear the terminal and move the cursor to the top left corner
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+// How long a MOVED_FROM waits for its MOVED_TO before the file counts as moved away
+const RENAME_TIMEOUT: Duration = Duration::from_millis(500);
+// How often inotify is checked while a rename is waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(tabled::Tabled)]
+struct ChangeEvent {
+  #[tabled(rename = "Time")]
+  time: String,
+  #[tabled(rename = "Event")]
+  event: &'static str,
+  #[tabled(rename = "Name")]
+  name: String,
+}
+
+// Redraw the listing every time inotify reports a change in the directory
+pub fn run(cli: &Cli, magic: &MagicDb) -> io::Result<()> {
+  let mut inotify = Inotify::init()?;
+  inotify.watches().add(
+    &cli.path,
+    WatchMask::CREATE
+      | WatchMask::MODIFY
+      | WatchMask::ATTRIB
+      | WatchMask::DELETE
+      | WatchMask::MOVED_FROM
+      | WatchMask::MOVED_TO
+      | WatchMask::DELETE_SELF
+      | WatchMask::MOVE_SELF,
+  )?;
+
+  let mut changelog: VecDeque<ChangeEvent> = VecDeque::with_capacity(cli.events);
+  let mut renames = Renames::default();
+  let mut buffer = [0; 4096];
+  let mut redraw = true;
+
+  loop {
+    if redraw {
+      draw(cli, magic, &changelog)?;
+    }
+
+    // Block until something happens, unless a rename is still waiting for its other half
+    let events: Vec<(EventMask, u32, String)> = if renames.is_waiting() {
+      match inotify.read_events(&mut buffer) {
+        Ok(events) => events.map(owned).collect(),
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+          thread::sleep(POLL_INTERVAL);
+          Vec::new()
+        }
+        Err(e) => return Err(e),
+      }
+    } else {
+      inotify
+        .read_events_blocking(&mut buffer)?
+        .map(owned)
+        .collect()
+    };
+
+    let now = Instant::now();
+    let mut batch = Vec::new();
+    for (mask, cookie, name) in events {
+      if mask.intersects(EventMask::DELETE_SELF | EventMask::MOVE_SELF) {
+        println!("{} was removed or moved, stopping", cli.path);
+        return Ok(());
+      }
+      batch.extend(renames.classify(mask, cookie, name, now));
+    }
+    batch.extend(renames.expire(now));
+    redraw = !batch.is_empty();
+
+    let time = Local::now().format("%H:%M:%S").to_string();
+    for (event, name) in batch {
+      // A file being written produces a stream of identical events
+      let repeated = changelog
+        .back()
+        .is_some_and(|last| last.event == event && last.name == name);
+      if repeated {
+        continue;
+      }
+
+      changelog.push_back(ChangeEvent {
+        time: time.clone(),
+        event,
+        name,
+      });
+      while changelog.len() > cli.events {
+        changelog.pop_front();
+      }
+    }
+  }
+}
+
+fn owned(event: Event<&OsStr>) -> (EventMask, u32, String) {
+  let name = event
+    .name
+    .map(|n| n.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  (event.mask, event.cookie, name)
+}
+
+// Renames arrive as a MOVED_FROM/MOVED_TO pair sharing a cookie, which may be split
+// across two reads. A MOVED_FROM is held until its partner arrives or it times out.
+#[derive(Default)]
+struct Renames {
+  pending: HashMap<u32, (String, Instant)>,
+}
+
+impl Renames {
+  fn is_waiting(&self) -> bool {
+    !self.pending.is_empty()
+  }
+
+  // The changelog entry for an event, or None if it is not shown or not complete yet
+  fn classify(
+    &mut self,
+    mask: EventMask,
+    cookie: u32,
+    name: String,
+    now: Instant,
+  ) -> Option<(&'static str, String)> {
+    if mask.contains(EventMask::MOVED_FROM) {
+      self.pending.insert(cookie, (name, now));
+      None
+    } else if mask.contains(EventMask::MOVED_TO) {
+      Some(match self.pending.remove(&cookie) {
+        Some((from, _)) => ("renamed", format!("{} -> {}", from, name)),
+        None => ("moved in", name),
+      })
+    } else if mask.contains(EventMask::CREATE) {
+      Some(("created", name))
+    } else if mask.contains(EventMask::DELETE) {
+      Some(("deleted", name))
+    } else if mask.contains(EventMask::MODIFY) {
+      Some(("modified", name))
+    } else if mask.contains(EventMask::ATTRIB) {
+      Some(("attributes", name))
+    } else {
+      None
+    }
+  }
+
+  // Files moved out of the directory, whose MOVED_TO never came
+  fn expire(&mut self, now: Instant) -> Vec<(&'static str, String)> {
+    let mut expired: Vec<(String, Instant)> = Vec::new();
+    self.pending.retain(|_, (name, since)| {
+      let waiting = now.duration_since(*since) < RENAME_TIMEOUT;
+      if !waiting {
+        expired.push((name.clone(), *since));
+      }
+      waiting
+    });
+    expired.sort_by_key(|(_, since)| *since);
+    expired
+      .into_iter()
+      .map(|(name, _)| ("moved away", name))
+      .collect()
+  }
+}
+
+fn draw(cli: &Cli, magic: &MagicDb, changelog: &VecDeque<ChangeEvent>) -> io::Result<()> {
+  let listing = render_listing(cli, magic)?;
+
+  let mut screen = String::from(CLEAR_SCREEN);
+  screen.push_str(&format!(
+    "Watching {} (Ctrl-C to quit)\n{}\n",
+    cli.path, listing
+  ));
+  if changelog.is_empty() {
+    screen.push_str("\nNo changes yet\n");
+  } else {
+    let rows: Vec<&ChangeEvent> = changelog.iter().rev().collect();
+    let log = Output::Table.render(&rows);
+    screen.push_str(&format!("\nRecent changes\n{}\n", log));
+  }
+
+  let mut stdout = io::stdout().lock();
+  stdout.write_all(screen.as_bytes())?;
+  stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn classifies_events() {
+    let mut renames = Renames::default();
+    let now = Instant::now();
+    let mut classify = |mask, name: &str| renames.classify(mask, 0, name.to_string(), now);
+    assert_eq!(
+      classify(EventMask::CREATE, "a"),
+      Some(("created", "a".to_string()))
+    );
+    assert_eq!(
+      classify(EventMask::DELETE, "a"),
+      Some(("deleted", "a".to_string()))
+    );
+    assert_eq!(
+      classify(EventMask::MODIFY, "a"),
+      Some(("modified", "a".to_string()))
+    );
+    assert_eq!(
+      classify(EventMask::ATTRIB, "a"),
+      Some(("attributes", "a".to_string()))
+    );
+    assert_eq!(
+      classify(EventMask::CREATE | EventMask::ISDIR, "dir"),
+      Some(("created", "dir".to_string()))
+    );
+    assert_eq!(
+      classify(EventMask::MOVED_TO, "b"),
+      Some(("moved in", "b".to_string()))
+    );
+    assert_eq!(classify(EventMask::ACCESS, "a"), None);
+  }
+
+  #[test]
+  fn pairs_renames_across_reads() {
+    let mut renames = Renames::default();
+    let start = Instant::now();
+    assert_eq!(
+      renames.classify(EventMask::MOVED_FROM, 7, "old".to_string(), start),
+      None
+    );
+    assert_eq!(
+      renames.classify(EventMask::MOVED_FROM, 8, "gone".to_string(), start),
+      None
+    );
+
+    // The next read, still within the timeout
+    let later = start + Duration::from_millis(100);
+    assert!(renames.expire(later).is_empty());
+    assert_eq!(
+      renames.classify(EventMask::MOVED_TO, 7, "new".to_string(), later),
+      Some(("renamed", "old -> new".to_string()))
+    );
+    assert!(renames.is_waiting());
+
+    assert_eq!(
+      renames.expire(start + RENAME_TIMEOUT),
+      [("moved away", "gone".to_string())]
+    );
+    assert!(!renames.is_waiting());
+  }
+}
@@ -0,0 +1,189 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use std::path::PathBuf;
+
+use crate::kill::parse_signal;
+
+/// List the running processes, read directly from /proc
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, name = "process")]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Commands>,
+  /// Where procfs is mounted; point it at a copied or fake tree to inspect that instead
+  #[arg(long, env = "PROC_ROOT", default_value = "/proc", global = true)]
+  pub proc_root: PathBuf,
+  /// Columns to show, separated by commas
+  #[arg(
+    short = 'o',
+    long,
+    value_enum,
+    value_delimiter = ',',
+    default_value = "pid,ppid,user,name,state,threads,rss,start,cmd"
+  )]
+  pub columns: Vec<Column>,
+  /// Show full command lines instead of cutting them to fit
+  #[arg(short, long)]
+  pub wide: bool,
+  /// Show the processes as a parent/child tree, like pstree
+  #[arg(short, long)]
+  pub tree: bool,
+  /// With --tree, show PIDs and do not collapse identical siblings
+  #[arg(long, requires = "tree")]
+  pub pids: bool,
+  /// With --tree, highlight the ancestry of this PID
+  #[arg(long, value_name = "PID", requires = "tree")]
+  pub highlight: Option<i32>,
+  /// Print a separate table for the host and for each container
+  #[arg(long)]
+  pub by_container: bool,
+  #[command(flatten)]
+  pub filters: Filters,
+}
+
+/// Which processes to show or signal, like the options of pgrep
+#[derive(Args, Debug, Default)]
+pub struct Filters {
+  /// Only processes owned by this user name or uid
+  #[arg(short, long)]
+  pub user: Option<String>,
+  /// Only processes whose name matches this regular expression
+  #[arg(short, long, value_name = "REGEX")]
+  pub name: Option<Regex>,
+  /// Only processes in one of these states, e.g. "RD" for running or waiting on I/O
+  #[arg(short, long, value_name = "STATES")]
+  pub state: Option<String>,
+  /// Only children of this parent PID
+  #[arg(short = 'P', long)]
+  pub ppid: Option<i32>,
+  /// Only processes in this container (ID prefix or LXC name), or "host" for the rest
+  #[arg(short, long)]
+  pub container: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+  /// A live view that refreshes like top, with CPU and memory usage
+  Top {
+    /// Seconds between refreshes
+    #[arg(short, long, default_value_t = 2.0)]
+    delay: f64,
+  },
+  /// List the open file descriptors of a process
+  Files {
+    /// Process to inspect
+    pid: i32,
+  },
+  /// List TCP, UDP and Unix sockets with the processes holding them
+  Net {
+    /// Only show sockets bound to this local port
+    #[arg(short, long)]
+    port: Option<u16>,
+  },
+  /// Show I/O, memory and scheduler statistics of a process, or record them to CSV
+  Stats {
+    /// Process to inspect
+    pid: i32,
+    /// Append a sample to this CSV file every interval instead of printing once
+    #[arg(short, long, value_name = "FILE")]
+    record: Option<PathBuf>,
+    /// Seconds between samples when recording
+    #[arg(short, long, default_value_t = 5.0, requires = "record")]
+    interval: f64,
+    /// Stop after this many samples instead of when the process exits
+    #[arg(short, long, requires = "record")]
+    count: Option<u64>,
+  },
+  /// Print processes being forked, exec'd and exiting as it happens
+  Watch {
+    /// Seconds between snapshots when falling back to polling
+    #[arg(short, long, default_value_t = 0.5)]
+    interval: f64,
+    /// Poll instead of using the netlink process connector
+    #[arg(long)]
+    poll: bool,
+  },
+  /// Send a signal to every process matching the filters, like pkill
+  Kill {
+    /// Signal to send, by name (TERM, SIGKILL) or number
+    #[arg(short = 'S', long, default_value = "TERM", value_parser = parse_signal)]
+    signal: i32,
+    /// Do not ask for confirmation
+    #[arg(short, long)]
+    yes: bool,
+    #[command(flatten)]
+    filters: Filters,
+  },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+  /// Process ID
+  Pid,
+  /// Parent process ID
+  Ppid,
+  /// Owner of the process (effective user)
+  User,
+  /// Command name (comm)
+  Name,
+  /// Scheduler state (R, S, D, Z, ...)
+  State,
+  /// Nice value
+  Nice,
+  /// Number of threads
+  Threads,
+  /// Resident set size in KiB
+  Rss,
+  /// Virtual memory size in KiB
+  Vsz,
+  /// When the process was started
+  Start,
+  /// Full command line
+  Cmd,
+  /// Control group path
+  Cgroup,
+  /// Container ID, or "-" for processes on the host
+  Container,
+  /// PID namespace inode
+  Pidns,
+  /// Network namespace inode
+  Netns,
+  /// Mount namespace inode
+  Mntns,
+  /// User namespace inode
+  Userns,
+  /// UTS (hostname) namespace inode
+  Utsns,
+  /// IPC namespace inode
+  Ipcns,
+  /// Cgroup namespace inode
+  Cgroupns,
+}
+
+impl Column {
+  // Header text and prettytable style for the column
+  pub fn header(self) -> (&'static str, &'static str) {
+    match self {
+      Column::Pid => ("PID", "bFg"),
+      Column::Ppid => ("PPID", "bFg"),
+      Column::User => ("User", "bFy"),
+      Column::Name => ("Name", "bFb"),
+      Column::State => ("State", "bFr"),
+      Column::Nice => ("Nice", "b"),
+      Column::Threads => ("Threads", "b"),
+      Column::Rss => ("RSS (KiB)", "bFc"),
+      Column::Vsz => ("VSZ (KiB)", "bFc"),
+      Column::Start => ("Started", "b"),
+      Column::Cmd => ("Command", "bFb"),
+      Column::Cgroup => ("Cgroup", "bFm"),
+      Column::Container => ("Container", "bFm"),
+      Column::Pidns => ("PID NS", "b"),
+      Column::Netns => ("Net NS", "b"),
+      Column::Mntns => ("Mount NS", "b"),
+      Column::Userns => ("User NS", "b"),
+      Column::Utsns => ("UTS NS", "b"),
+      Column::Ipcns => ("IPC NS", "b"),
+      Column::Cgroupns => ("Cgroup NS", "b"),
+    }
+  }
+}
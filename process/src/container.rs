@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::Path;
+
+/// Namespace inode numbers from /proc/<pid>/ns; two processes share a
+/// namespace when the numbers are equal. None when the link cannot be read,
+/// which for other users' processes needs root.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Namespaces {
+  pub cgroup: Option<u64>,
+  pub ipc: Option<u64>,
+  pub mnt: Option<u64>,
+  pub net: Option<u64>,
+  pub pid: Option<u64>,
+  pub user: Option<u64>,
+  pub uts: Option<u64>,
+}
+
+impl Namespaces {
+  pub fn read(proc_path: &Path) -> Namespaces {
+    let ns = proc_path.join("ns");
+    // The links look like "pid:[4026531836]"
+    let inode = |name: &str| {
+      let target = fs::read_link(ns.join(name)).ok()?;
+      let target = target.to_str()?;
+      target
+        .strip_prefix(name)?
+        .strip_prefix(":[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+    };
+
+    Namespaces {
+      cgroup: inode("cgroup"),
+      ipc: inode("ipc"),
+      mnt: inode("mnt"),
+      net: inode("net"),
+      pid: inode("pid"),
+      user: inode("user"),
+      uts: inode("uts"),
+    }
+  }
+
+  /// The namespaces of this process, to compare others against
+  pub fn own(proc_root: &Path) -> Namespaces {
+    Namespaces::read(&proc_root.join("self"))
+  }
+}
+
+/// The cgroup path of a process from /proc/<pid>/cgroup.
+/// With cgroup v2 there is a single "0::/path" line; on v1 or hybrid systems
+/// every hierarchy has its own line and the deepest path is the most telling.
+pub fn read_cgroup(proc_path: &Path) -> String {
+  let content = fs::read_to_string(proc_path.join("cgroup")).unwrap_or_default();
+  content
+    .lines()
+    // hierarchy-ID:controller-list:cgroup-path
+    .filter_map(|line| line.splitn(3, ':').nth(2))
+    .max_by_key(|path| path.len())
+    .unwrap_or("/")
+    .to_string()
+}
+
+/// The container a cgroup path belongs to, if any: the short (12 character) ID
+/// for Docker, Podman, containerd and CRI-O, or the name for LXC.
+pub fn container_id(cgroup: &str) -> Option<String> {
+  let components: Vec<&str> = cgroup.split('/').collect();
+  for (index, component) in components.iter().enumerate() {
+    // systemd names the scope "docker-<id>.scope", cgroupfs drivers use "<id>"
+    let name = component.strip_suffix(".scope").unwrap_or(component);
+    let name = ["docker-", "libpod-", "cri-containerd-", "crio-"]
+      .iter()
+      .find_map(|prefix| name.strip_prefix(prefix))
+      .unwrap_or(name);
+    if name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+      return Some(name[..12].to_string());
+    }
+
+    // LXC uses "/lxc/<name>" or "/lxc.payload.<name>"
+    if let Some(name) = component.strip_prefix("lxc.payload.") {
+      return Some(name.to_string());
+    }
+    if *component == "lxc"
+      && let Some(name) = components.get(index + 1)
+      && !name.is_empty()
+    {
+      return Some(name.to_string());
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fixture(pid: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("tests/fixtures/proc")
+      .join(pid)
+  }
+
+  #[test]
+  fn container_ids_from_cgroup_paths() {
+    let id = "3f2a9c1b7d4e5b6a5b6a5b6a5b6a5b6a5b6a5b6a5b6a5b6a5b6a5b6a5b6a5b6a";
+    for path in [
+      format!("/docker/{}", id),
+      format!("/system.slice/docker-{}.scope", id),
+      format!("/machine.slice/libpod-{}.scope/container", id),
+      format!("/kubepods/burstable/pod1234/{}", id),
+      format!(
+        "/kubepods.slice/kubepods-pod1.slice/cri-containerd-{}.scope",
+        id
+      ),
+    ] {
+      assert_eq!(
+        container_id(&path).as_deref(),
+        Some("3f2a9c1b7d4e"),
+        "{}",
+        path
+      );
+    }
+    assert_eq!(container_id("/lxc/web01").as_deref(), Some("web01"));
+    assert_eq!(
+      container_id("/lxc.payload.web01/init.scope").as_deref(),
+      Some("web01")
+    );
+
+    assert_eq!(container_id("/"), None);
+    assert_eq!(
+      container_id("/user.slice/user-1000.slice/session-2.scope"),
+      None
+    );
+    // Too short to be a container ID
+    assert_eq!(container_id("/docker/3f2a9c1b7d4e"), None);
+  }
+
+  #[test]
+  fn deepest_cgroup_path_wins_on_hybrid_systems() {
+    assert_eq!(read_cgroup(&fixture("1")), "/init.scope");
+    assert_eq!(
+      read_cgroup(&fixture("42")),
+      "/user.slice/user-1000.slice/session-2.scope"
+    );
+    assert_eq!(read_cgroup(&fixture("nonexistent")), "/");
+  }
+
+  #[test]
+  fn namespaces_from_links() {
+    let host = Namespaces::read(&fixture("1"));
+    let container = Namespaces::read(&fixture("100"));
+    assert_eq!(host.pid, Some(4026531836));
+    assert_eq!(container.pid, Some(4026532304));
+    assert_eq!(host.user, container.user);
+    assert_ne!(host.net, container.net);
+
+    // "self" links to PID 1 in the fixture
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc");
+    assert_eq!(Namespaces::own(&root), host);
+    assert_eq!(
+      Namespaces::read(&fixture("nonexistent")),
+      Namespaces::default()
+    );
+  }
+}
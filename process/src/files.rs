@@ -0,0 +1,113 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+
+/// An open file descriptor from /proc/<pid>/fd
+pub struct FileDescriptor {
+  pub fd: i32,
+  /// file, dir, socket, pipe, anon_inode, ...
+  pub kind: &'static str,
+  /// r, w or rw, from the flags in /proc/<pid>/fdinfo
+  pub access: &'static str,
+  /// What the descriptor points at, as the kernel shows it
+  pub target: String,
+}
+
+/// Every descriptor of a process, sorted by number.
+/// Fails if the process does not exist or belongs to another user.
+pub fn read_fds(proc_root: &Path, pid: i32) -> io::Result<Vec<FileDescriptor>> {
+  let proc_path = proc_root.join(pid.to_string());
+  let mut fds = Vec::new();
+
+  for entry in fs::read_dir(proc_path.join("fd"))? {
+    let entry = entry?;
+    let Some(fd) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+      continue;
+    };
+    // The descriptor may be closed between listing and reading it
+    let Ok(target) = fs::read_link(entry.path()) else {
+      continue;
+    };
+    let target = target.to_string_lossy().into_owned();
+
+    fds.push(FileDescriptor {
+      fd,
+      kind: kind_of(&entry.path(), &target),
+      access: access_of(&proc_path, fd),
+      target,
+    });
+  }
+
+  fds.sort_by_key(|fd| fd.fd);
+  Ok(fds)
+}
+
+// Anonymous objects are recognised by their target, anything else by
+// following the link to the file itself
+fn kind_of(fd_path: &Path, target: &str) -> &'static str {
+  if target.starts_with("socket:[") {
+    return "socket";
+  }
+  if target.starts_with("pipe:[") {
+    return "pipe";
+  }
+  if target.starts_with("anon_inode:") {
+    return "anon_inode";
+  }
+
+  match fs::metadata(fd_path).map(|metadata| metadata.file_type()) {
+    Ok(t) if t.is_dir() => "dir",
+    Ok(t) if t.is_file() => "file",
+    Ok(t) if t.is_char_device() => "char",
+    Ok(t) if t.is_block_device() => "block",
+    Ok(t) if t.is_fifo() => "fifo",
+    Ok(t) if t.is_socket() => "socket",
+    _ => "unknown",
+  }
+}
+
+// The access mode bits of the "flags:" line, which is in octal
+fn access_of(proc_path: &Path, fd: i32) -> &'static str {
+  let flags = fs::read_to_string(proc_path.join("fdinfo").join(fd.to_string()))
+    .ok()
+    .and_then(|info| {
+      info
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|value| i32::from_str_radix(value.trim(), 8).ok())
+    });
+
+  match flags.map(|flags| flags & libc::O_ACCMODE) {
+    Some(libc::O_RDONLY) => "r",
+    Some(libc::O_WRONLY) => "w",
+    Some(libc::O_RDWR) => "rw",
+    _ => "?",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn descriptors_from_fixture() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc");
+    let fds = read_fds(&root, 42).unwrap();
+    let summary: Vec<(i32, &str, &str, &str)> = fds
+      .iter()
+      .map(|fd| (fd.fd, fd.kind, fd.access, fd.target.as_str()))
+      .collect();
+    assert_eq!(
+      summary,
+      [
+        (0, "char", "r", "/dev/null"),
+        (3, "socket", "rw", "socket:[1001]"),
+        (4, "pipe", "w", "pipe:[2002]"),
+        (5, "anon_inode", "rw", "anon_inode:[eventfd]"),
+      ]
+    );
+
+    assert!(read_fds(&root, 99999).is_err());
+  }
+}
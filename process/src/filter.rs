@@ -0,0 +1,152 @@
+use crate::cli::Filters;
+use crate::info::{ProcessInfo, SystemInfo};
+
+impl Filters {
+  /// True when no filter was given, so every process matches
+  pub fn is_empty(&self) -> bool {
+    self.user.is_none()
+      && self.name.is_none()
+      && self.state.is_none()
+      && self.ppid.is_none()
+      && self.container.is_none()
+  }
+
+  /// Whether a process passes every filter that was given
+  pub fn matches(&self, process: &ProcessInfo, system: &SystemInfo) -> bool {
+    if let Some(user) = &self.user {
+      // A process whose owner could not be read matches no user
+      let Some(uid) = process.uid else {
+        return false;
+      };
+      let matched = match user.parse::<u32>() {
+        Ok(wanted) => uid == wanted,
+        Err(_) => system.user_name(uid) == *user,
+      };
+      if !matched {
+        return false;
+      }
+    }
+    if let Some(name) = &self.name
+      && !name.is_match(&process.name)
+    {
+      return false;
+    }
+    if let Some(states) = &self.state
+      && !process.state.chars().all(|c| states.contains(c))
+    {
+      return false;
+    }
+    if let Some(container) = &self.container {
+      let matched = match &process.container {
+        Some(id) => id.starts_with(container.as_str()),
+        None => container == "host",
+      };
+      if !matched {
+        return false;
+      }
+    }
+    self.ppid.is_none_or(|ppid| process.ppid == ppid)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::info::tests::fixture_system;
+  use crate::info::{get_process_info, read_processes};
+  use regex::Regex;
+
+  fn matching(filters: &Filters) -> Vec<i32> {
+    let system = fixture_system();
+    read_processes(&system)
+      .unwrap()
+      .iter()
+      .filter(|process| filters.matches(process, &system))
+      .map(|process| process.pid)
+      .collect()
+  }
+
+  #[test]
+  fn no_filters_match_everything() {
+    let filters = Filters::default();
+    assert!(filters.is_empty());
+    assert_eq!(matching(&filters), [1, 2, 7, 42, 100, 101, 102]);
+  }
+
+  #[test]
+  fn single_filters() {
+    let by_user = |user: &str| Filters {
+      user: Some(user.to_string()),
+      ..Filters::default()
+    };
+    assert_eq!(matching(&by_user("alice")), [42]);
+    assert_eq!(matching(&by_user("1000")), [42]);
+    assert!(matching(&by_user("bob")).is_empty());
+
+    let by_name = Filters {
+      name: Some(Regex::new("^ng").unwrap()),
+      ..Filters::default()
+    };
+    assert!(!by_name.is_empty());
+    assert_eq!(matching(&by_name), [100, 101, 102]);
+
+    let by_state = Filters {
+      state: Some("RI".to_string()),
+      ..Filters::default()
+    };
+    assert_eq!(matching(&by_state), [7, 42]);
+
+    let by_parent = Filters {
+      ppid: Some(100),
+      ..Filters::default()
+    };
+    assert_eq!(matching(&by_parent), [101, 102]);
+  }
+
+  #[test]
+  fn unknown_owners_match_no_user() {
+    let system = fixture_system();
+    // A process whose status cannot be read, as when it belongs to another user
+    let dir = std::env::temp_dir().join(format!("process_no_status_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(system.proc_root.join("1/stat"), dir.join("stat")).unwrap();
+    let process = get_process_info(&dir, &system).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(process.uid, None);
+    assert_eq!(process.user, "?");
+    for user in ["root", "0", "?"] {
+      let filters = Filters {
+        user: Some(user.to_string()),
+        ..Filters::default()
+      };
+      assert!(!filters.matches(&process, &system), "{}", user);
+    }
+  }
+
+  #[test]
+  fn containers_and_the_host() {
+    let in_container = Filters {
+      container: Some("3f2a".to_string()),
+      ..Filters::default()
+    };
+    assert_eq!(matching(&in_container), [100, 101, 102]);
+
+    let on_host = Filters {
+      container: Some("host".to_string()),
+      ..Filters::default()
+    };
+    assert_eq!(matching(&on_host), [1, 2, 7, 42]);
+  }
+
+  #[test]
+  fn every_filter_must_match() {
+    let filters = Filters {
+      user: Some("root".to_string()),
+      name: Some(Regex::new("nginx").unwrap()),
+      ppid: Some(1),
+      ..Filters::default()
+    };
+    assert_eq!(matching(&filters), [100]);
+  }
+}
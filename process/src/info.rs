@@ -0,0 +1,244 @@
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::container::{self, Namespaces};
+use crate::stat::Stat;
+
+/// Details of one process, collected from /proc/<pid>/{stat,status,cmdline,cgroup,ns}
+pub struct ProcessInfo {
+  pub pid: i32,
+  pub ppid: i32,
+  pub name: String,
+  pub state: String,
+  /// None when /proc/<pid>/status cannot be read
+  pub uid: Option<u32>,
+  /// The user name, the uid without a passwd entry, or "?" when the uid is unknown
+  pub user: String,
+  pub cmdline: Vec<String>,
+  pub start_time: DateTime<Local>,
+  /// The start time in clock ticks after boot; with the PID it identifies one process
+  pub starttime: u64,
+  pub nice: i64,
+  pub threads: i64,
+  /// CPU time spent in user and kernel mode, in clock ticks
+  pub utime: u64,
+  pub stime: u64,
+  /// Resident set size in KiB
+  pub rss: u64,
+  /// Virtual memory size in KiB
+  pub vsz: u64,
+  pub cgroup: String,
+  /// Short ID or name of the container the process runs in, None on the host
+  pub container: Option<String>,
+  pub namespaces: Namespaces,
+}
+
+/// System wide values needed to interpret the per-process numbers
+pub struct SystemInfo {
+  /// Where procfs is mounted, normally /proc
+  pub proc_root: PathBuf,
+  /// Boot time in seconds since the epoch (btime in /proc/stat)
+  pub boot_time: u64,
+  /// Clock ticks per second, the unit of the times in /proc/<pid>/stat
+  pub clock_ticks: u64,
+  pub page_size: u64,
+  /// User names by uid, from /etc/passwd
+  pub users: HashMap<u32, String>,
+}
+
+impl SystemInfo {
+  pub fn read(proc_root: &Path) -> SystemInfo {
+    let boot_time = fs::read_to_string(proc_root.join("stat"))
+      .ok()
+      .and_then(|stat| {
+        stat
+          .lines()
+          .find_map(|line| line.strip_prefix("btime "))
+          .and_then(|value| value.trim().parse().ok())
+      })
+      .unwrap_or(0);
+
+    // SAFETY: sysconf has no preconditions and only reads configuration values
+    let (clock_ticks, page_size) = unsafe {
+      (
+        libc::sysconf(libc::_SC_CLK_TCK),
+        libc::sysconf(libc::_SC_PAGESIZE),
+      )
+    };
+
+    SystemInfo {
+      proc_root: proc_root.to_path_buf(),
+      boot_time,
+      clock_ticks: clock_ticks.max(1) as u64,
+      page_size: page_size.max(1) as u64,
+      users: read_users(),
+    }
+  }
+
+  /// The name for a uid, or the number itself when it has no passwd entry
+  pub fn user_name(&self, uid: u32) -> String {
+    self
+      .users
+      .get(&uid)
+      .cloned()
+      .unwrap_or_else(|| uid.to_string())
+  }
+}
+
+/// Read every process in the proc root, sorted by PID.
+/// Returns None if the proc root itself cannot be read.
+pub fn read_processes(system: &SystemInfo) -> Option<Vec<ProcessInfo>> {
+  let entries = fs::read_dir(&system.proc_root).ok()?;
+
+  let mut processes = Vec::new();
+  for entry in entries.filter_map(Result::ok) {
+    let path = entry.path();
+
+    if path.is_dir() {
+      // Check if the folder name is a number (i.e., PID)
+      if let Some(dir_name_str) = path.file_name().and_then(|s| s.to_str())
+        && dir_name_str.chars().all(char::is_numeric)
+      {
+        // Getting the information about the process
+        if let Some(process) = get_process_info(&path, system) {
+          processes.push(process);
+        }
+      }
+    }
+  }
+
+  // read_dir returns the processes in no particular order
+  processes.sort_by_key(|process| process.pid);
+  Some(processes)
+}
+
+/// A function that receives a path to a process and returns its details
+/// It returns Option - that is, it will either return the information, or nothing (in case of an error)
+pub fn get_process_info(proc_path: &Path, system: &SystemInfo) -> Option<ProcessInfo> {
+  let stat = Stat::parse(&fs::read_to_string(proc_path.join("stat")).ok()?)?; // The ? will return None if the read failed
+
+  let start_secs = system.boot_time + stat.starttime / system.clock_ticks;
+  let start_time = DateTime::from_timestamp(start_secs as i64, 0)?.with_timezone(&Local);
+
+  let uid = read_uid(proc_path);
+  let cmdline = fs::read(proc_path.join("cmdline"))
+    .map(|raw| {
+      raw
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+    })
+    .unwrap_or_default();
+  let cgroup = container::read_cgroup(proc_path);
+
+  Some(ProcessInfo {
+    pid: stat.pid,
+    ppid: stat.ppid,
+    name: stat.comm,
+    state: stat.state.to_string(),
+    uid,
+    user: uid.map_or_else(|| "?".to_string(), |uid| system.user_name(uid)),
+    cmdline,
+    start_time,
+    starttime: stat.starttime,
+    nice: stat.nice,
+    threads: stat.num_threads,
+    utime: stat.utime,
+    stime: stat.stime,
+    rss: stat.rss.max(0) as u64 * system.page_size / 1024,
+    vsz: stat.vsize / 1024,
+    container: container::container_id(&cgroup),
+    cgroup,
+    namespaces: Namespaces::read(proc_path),
+  })
+}
+
+impl ProcessInfo {
+  /// The command line, or the name in brackets for kernel threads (like ps)
+  pub fn command(&self) -> String {
+    if self.cmdline.is_empty() {
+      format!("[{}]", self.name)
+    } else {
+      self.cmdline.join(" ")
+    }
+  }
+}
+
+// The effective uid from the "Uid:" line of /proc/<pid>/status
+fn read_uid(proc_path: &Path) -> Option<u32> {
+  let status = fs::read_to_string(proc_path.join("status")).ok()?;
+  let line = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+  line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn read_users() -> HashMap<u32, String> {
+  let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+  passwd
+    .lines()
+    .filter_map(|line| {
+      let fields: Vec<&str> = line.split(':').collect();
+      Some((fields.get(2)?.parse().ok()?, fields[0].to_string()))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+
+  /// The fake /proc tree in tests/fixtures/proc, with fixed user names
+  pub fn fixture_system() -> SystemInfo {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc");
+    let mut system = SystemInfo::read(&root);
+    system.users = HashMap::from([(0, "root".to_string()), (1000, "alice".to_string())]);
+    system
+  }
+
+  #[test]
+  fn reads_every_process_in_the_fixture() {
+    let system = fixture_system();
+    assert_eq!(system.boot_time, 1700000000);
+
+    let processes = read_processes(&system).unwrap();
+    let pids: Vec<i32> = processes.iter().map(|process| process.pid).collect();
+    assert_eq!(pids, [1, 2, 7, 42, 100, 101, 102]);
+
+    let firefox = &processes[3];
+    assert_eq!(firefox.name, "Web Content");
+    assert_eq!(firefox.state, "R");
+    assert_eq!(firefox.ppid, 1);
+    assert_eq!(firefox.uid, Some(1000));
+    assert_eq!(firefox.user, "alice");
+    assert_eq!(firefox.threads, 27);
+    assert_eq!(firefox.vsz, 3145728);
+    assert_eq!(firefox.rss, 51200 * system.page_size / 1024);
+    assert_eq!(
+      firefox.command(),
+      "/usr/lib/firefox/firefox -contentproc -childID 1"
+    );
+    assert_eq!(
+      firefox.start_time.timestamp() as u64,
+      1700000000 + 5000 / system.clock_ticks
+    );
+    assert_eq!(firefox.container, None);
+  }
+
+  #[test]
+  fn kernel_threads_have_no_command_line() {
+    let processes = read_processes(&fixture_system()).unwrap();
+    let kworker = processes.iter().find(|process| process.pid == 7).unwrap();
+    assert!(kworker.cmdline.is_empty());
+    assert_eq!(kworker.command(), "[kworker/0:1-events]");
+    assert_eq!(kworker.state, "I");
+  }
+
+  #[test]
+  fn missing_root_is_an_error() {
+    let mut system = fixture_system();
+    system.proc_root = PathBuf::from("/nonexistent/proc");
+    assert!(read_processes(&system).is_none());
+  }
+}
@@ -0,0 +1,126 @@
+use std::io;
+use std::path::Path;
+
+use crate::info::ProcessInfo;
+use crate::stat::Stat;
+
+// The signals worth sending by name; anything else can still be given as a number
+const SIGNALS: [(&str, i32); 15] = [
+  ("HUP", libc::SIGHUP),
+  ("INT", libc::SIGINT),
+  ("QUIT", libc::SIGQUIT),
+  ("ABRT", libc::SIGABRT),
+  ("KILL", libc::SIGKILL),
+  ("USR1", libc::SIGUSR1),
+  ("SEGV", libc::SIGSEGV),
+  ("USR2", libc::SIGUSR2),
+  ("PIPE", libc::SIGPIPE),
+  ("ALRM", libc::SIGALRM),
+  ("TERM", libc::SIGTERM),
+  ("CONT", libc::SIGCONT),
+  ("STOP", libc::SIGSTOP),
+  ("TSTP", libc::SIGTSTP),
+  ("WINCH", libc::SIGWINCH),
+];
+
+/// Parse a signal given as "TERM", "SIGTERM", "term" or "15"
+pub fn parse_signal(text: &str) -> Result<i32, String> {
+  if let Ok(number) = text.parse::<i32>() {
+    return if (0..=libc::SIGRTMAX()).contains(&number) {
+      Ok(number)
+    } else {
+      Err(format!("no signal number {}", number))
+    };
+  }
+
+  let upper = text.to_ascii_uppercase();
+  let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+  SIGNALS
+    .iter()
+    .find(|(known, _)| *known == name)
+    .map(|&(_, number)| number)
+    .ok_or_else(|| format!("unknown signal '{}'", text))
+}
+
+/// "SIGTERM" for 15, or the bare number for signals without a name here
+pub fn signal_name(signal: i32) -> String {
+  SIGNALS
+    .iter()
+    .find(|&&(_, number)| number == signal)
+    .map_or_else(|| signal.to_string(), |(name, _)| format!("SIG{}", name))
+}
+
+/// Whether the PID still belongs to the process that was listed: it has the same
+/// start time and name, so a PID reused in the meantime is not mistaken for it
+pub fn is_same_process(proc_root: &Path, process: &ProcessInfo) -> bool {
+  Stat::read(proc_root, process.pid)
+    .is_some_and(|stat| stat.starttime == process.starttime && stat.comm == process.name)
+}
+
+/// Send a signal to one process
+pub fn send(pid: i32, signal: i32) -> io::Result<()> {
+  // SAFETY: kill only takes plain integers and reports failure through errno
+  if unsafe { libc::kill(pid, signal) } == 0 {
+    Ok(())
+  } else {
+    Err(io::Error::last_os_error())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::info::read_processes;
+  use crate::info::tests::fixture_system;
+
+  #[test]
+  fn parses_names_and_numbers() {
+    assert_eq!(parse_signal("TERM"), Ok(libc::SIGTERM));
+    assert_eq!(parse_signal("SIGKILL"), Ok(libc::SIGKILL));
+    assert_eq!(parse_signal("hup"), Ok(libc::SIGHUP));
+    assert_eq!(parse_signal("sigusr1"), Ok(libc::SIGUSR1));
+    assert_eq!(parse_signal("9"), Ok(9));
+    assert_eq!(parse_signal("0"), Ok(0));
+    assert_eq!(
+      parse_signal(&libc::SIGRTMAX().to_string()),
+      Ok(libc::SIGRTMAX())
+    );
+  }
+
+  #[test]
+  fn rejects_unknown_signals() {
+    assert_eq!(parse_signal("FOO"), Err("unknown signal 'FOO'".to_string()));
+    assert_eq!(parse_signal("SIG"), Err("unknown signal 'SIG'".to_string()));
+    assert_eq!(parse_signal("-1"), Err("no signal number -1".to_string()));
+    let too_high = (libc::SIGRTMAX() + 1).to_string();
+    assert!(parse_signal(&too_high).is_err());
+  }
+
+  #[test]
+  fn names_signals() {
+    assert_eq!(signal_name(libc::SIGTERM), "SIGTERM");
+    assert_eq!(signal_name(libc::SIGRTMIN()), libc::SIGRTMIN().to_string());
+  }
+
+  #[test]
+  fn notices_a_reused_pid() {
+    let system = fixture_system();
+    let processes = read_processes(&system).unwrap();
+    let mut nginx = processes
+      .into_iter()
+      .find(|process| process.pid == 100)
+      .unwrap();
+    assert!(is_same_process(&system.proc_root, &nginx));
+
+    nginx.starttime += 1;
+    assert!(!is_same_process(&system.proc_root, &nginx));
+    nginx.starttime -= 1;
+
+    nginx.name = "bash".to_string();
+    assert!(!is_same_process(&system.proc_root, &nginx));
+    nginx.name = "nginx".to_string();
+
+    nginx.pid = 99999;
+    assert!(!is_same_process(&system.proc_root, &nginx));
+  }
+}
@@ -1,63 +1,459 @@
-use std::fs;
-use std::path::Path;
+use chrono::Local;
+use clap::Parser;
 // Import the necessary components from the prettytable library
-use prettytable::{Cell, Row, Table};
+use prettytable::{Cell, Row, Table, row};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+mod cli;
+mod container;
+mod files;
+mod filter;
+mod info;
+mod kill;
+mod net;
+mod stat;
+mod stats;
+mod top;
+mod tree;
+mod watch;
+
+use cli::{Cli, Column, Commands, Filters};
+use container::Namespaces;
+use info::{ProcessInfo, SystemInfo, read_processes};
+use net::Socket;
+use stat::Stat;
+use stats::ProcessStats;
+use tree::TreeOptions;
 
 fn main() {
-  // 1. Create a new table object
-  let mut table = Table::new();
+  let cli = Cli::parse();
+  let system = SystemInfo::read(&cli.proc_root);
 
-  // 2. Adding the header row to the table
-  table.add_row(Row::new(vec![
-    Cell::new("PID").style_spec("bFg"),  // b = bold, Fg = a(uto) green
-    Cell::new("Name").style_spec("bFb"), // b = bold, Fb = a(uto) blue
-    Cell::new("State").style_spec("bFr"), // b = bold, Fr = a(uto) red
-  ]));
-
-  // Read all records in the /proc folder
-  if let Ok(entries) = fs::read_dir("/proc") {
-    for entry in entries.filter_map(Result::ok) {
-      let path = entry.path();
-
-      if path.is_dir() {
-        // Check if the folder name is a number (i.e., PID)
-        if let Some(dir_name_str) = path.file_name().and_then(|s| s.to_str()) {
-          if dir_name_str.chars().all(char::is_numeric) {
-            // 3. Getting the information about the process and adding it to the table
-            if let Some((pid, name, state)) = get_process_info(&path) {
-              table.add_row(Row::new(vec![
-                Cell::new(&pid),
-                Cell::new(&name),
-                Cell::new(&state),
-              ]));
-            }
-          }
-        }
+  match cli.command {
+    Some(Commands::Top { delay }) => {
+      if let Err(e) = top::run(&system, Duration::from_secs_f64(delay.max(0.1))) {
+        eprintln!("Error: {}", e);
       }
+      return;
     }
-  } else {
-    eprintln!("Error: Could not read /proc directory.");
+    Some(Commands::Files { pid }) => {
+      if let Err(e) = list_files(&system.proc_root, pid) {
+        eprintln!("Error: Could not read descriptors of {}: {}", pid, e);
+        std::process::exit(1);
+      }
+      return;
+    }
+    Some(Commands::Net { port }) => {
+      list_sockets(&system.proc_root, port);
+      return;
+    }
+    Some(Commands::Stats {
+      pid, record: None, ..
+    }) => {
+      if !show_stats(&system.proc_root, pid) {
+        eprintln!("Error: No process with PID {}", pid);
+        std::process::exit(1);
+      }
+      return;
+    }
+    Some(Commands::Stats {
+      pid,
+      record: Some(ref file),
+      interval,
+      count,
+    }) => {
+      let interval = Duration::from_secs_f64(interval.max(0.0));
+      if let Err(e) = record_stats(&system.proc_root, pid, file, interval, count) {
+        eprintln!("Error: Could not record to {}: {}", file.display(), e);
+        std::process::exit(1);
+      }
+      return;
+    }
+    Some(Commands::Watch { interval, poll }) => {
+      let interval = Duration::from_secs_f64(interval.max(0.05));
+      if let Err(e) = watch::run(&system, interval, poll) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+      }
+      return;
+    }
+    Some(Commands::Kill {
+      signal,
+      yes,
+      ref filters,
+    }) => match kill_matching(&system, filters, signal, yes) {
+      Ok(code) => std::process::exit(code),
+      Err(e) => {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+      }
+    },
+    None => {}
+  }
+
+  if cli.tree {
+    let Some(mut processes) = read_processes(&system) else {
+      eprintln!("Error: Could not read {}", system.proc_root.display());
+      return;
+    };
+    if !cli.filters.is_empty() {
+      keep_with_ancestors(&mut processes, |process| {
+        cli.filters.matches(process, &system)
+      });
+    }
+    let options = TreeOptions {
+      pids: cli.pids,
+      highlight: cli.highlight,
+    };
+    println!("{}", tree::render(&processes, &options));
     return;
   }
 
+  // 1. Read all records in the /proc folder and keep the ones passing the filters
+  let Some(processes) = read_processes(&system) else {
+    eprintln!("Error: Could not read {}", system.proc_root.display());
+    return;
+  };
+  let processes: Vec<&ProcessInfo> = processes
+    .iter()
+    .filter(|process| cli.filters.matches(process, &system))
+    .collect();
+  let own = Namespaces::own(&system.proc_root);
+
+  // 2. Print them in one table, or one table per container with the host first
+  if cli.by_container {
+    let mut groups: BTreeMap<Option<&str>, Vec<&ProcessInfo>> = BTreeMap::new();
+    for process in processes {
+      groups
+        .entry(process.container.as_deref())
+        .or_default()
+        .push(process);
+    }
+    for (container, members) in groups {
+      match container {
+        Some(id) => println!("Container {} ({} processes)", id, members.len()),
+        None => println!("Host ({} processes)", members.len()),
+      }
+      print_processes(&cli, &members, &own);
+    }
+  } else {
+    print_processes(&cli, &processes, &own);
+  }
+}
+
+// Print the selected columns of the processes as a table. Processes in another
+// PID namespace than this one, which is usually a container, are shown in magenta.
+fn print_processes(cli: &Cli, processes: &[&ProcessInfo], own: &Namespaces) {
+  // 1. Create a new table object
+  let mut table = Table::new();
+
+  // 2. Adding the header row to the table, one cell per selected column
+  table.add_row(Row::new(
+    cli
+      .columns
+      .iter()
+      .map(|column| {
+        let (title, style) = column.header();
+        Cell::new(title).style_spec(style)
+      })
+      .collect(),
+  ));
+
+  // 3. Add a row for every process
+  for process in processes {
+    let foreign =
+      own.pid.is_some() && process.namespaces.pid.is_some() && process.namespaces.pid != own.pid;
+    table.add_row(Row::new(
+      cli
+        .columns
+        .iter()
+        .map(|&column| {
+          let cell = Cell::new(&cell_text(process, column, cli.wide));
+          if foreign { cell.style_spec("Fm") } else { cell }
+        })
+        .collect(),
+    ));
+  }
+
   // 4. Print the entire table to the screen
   table.printstd();
 }
 
-/// A function that receives a path to a process and returns its details
-/// It returns Option - that is, it will either return the information, or nothing (in case of an error)
-fn get_process_info(proc_path: &Path) -> Option<(String, String, String)> {
-  let stat_path = proc_path.join("stat");
-  let stat_content = fs::read_to_string(stat_path).ok()?; // The ? will return None if the read failed
+// Print the statistics of one process; false if it does not exist
+fn show_stats(proc_root: &Path, pid: i32) -> bool {
+  let Some(stats) = ProcessStats::read(proc_root, pid) else {
+    return false;
+  };
 
-  let fields: Vec<&str> = stat_content.split_whitespace().collect();
+  let mut table = Table::new();
+  table.add_row(row![bFb->"Statistic", bFg->"Value", b->"Unit"]);
+  for (name, unit, value) in stats.fields() {
+    let value = value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    table.add_row(row![name, r->value, unit]);
+  }
+  table.printstd();
+  true
+}
 
-  if fields.len() > 2 {
-    let pid = fields[0].to_string();
-    let name = fields[1].trim_matches(|c| c == '(' || c == ')').to_string();
-    let state = fields[2].to_string();
-    Some((pid, name, state))
+// Append a CSV row per interval until the process exits or `count` rows are written.
+// A new or empty file gets a header first.
+fn record_stats(
+  proc_root: &Path,
+  pid: i32,
+  file: &Path,
+  interval: Duration,
+  count: Option<u64>,
+) -> io::Result<()> {
+  let mut out = OpenOptions::new().create(true).append(true).open(file)?;
+  if out.metadata()?.len() == 0 {
+    writeln!(out, "{}", ProcessStats::csv_header())?;
+  }
+  eprintln!(
+    "Recording PID {} to {} every {:.1}s, press Ctrl-C to stop",
+    pid,
+    file.display(),
+    interval.as_secs_f64()
+  );
+
+  let mut samples = 0;
+  // The start time tells the process apart from a later one that gets the same PID
+  let mut started = None;
+  while count.is_none_or(|count| samples < count) {
+    if samples > 0 {
+      thread::sleep(interval);
+    }
+    let Some(stats) = ProcessStats::read(proc_root, pid) else {
+      eprintln!("Process {} is gone after {} samples", pid, samples);
+      break;
+    };
+    let starttime = Stat::read(proc_root, pid).map(|stat| stat.starttime);
+    if samples == 0 {
+      started = starttime;
+    } else if starttime != started {
+      eprintln!(
+        "Process {} is gone after {} samples, its PID now belongs to another process",
+        pid, samples
+      );
+      break;
+    }
+    let timestamp = Local::now().format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+    writeln!(out, "{}", stats.csv_row(&timestamp))?;
+    // Flush every row so the file can be charted while recording goes on
+    out.flush()?;
+    samples += 1;
+  }
+  Ok(())
+}
+
+// Keep the matching processes and the chain of parents leading to each of them,
+// so a filtered tree still hangs together
+fn keep_with_ancestors(processes: &mut Vec<ProcessInfo>, matches: impl Fn(&ProcessInfo) -> bool) {
+  let parents: HashMap<i32, i32> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+  let mut keep = HashSet::new();
+  for process in processes.iter().filter(|process| matches(process)) {
+    let mut pid = process.pid;
+    // insert returns false once we reach a branch that is already kept
+    while keep.insert(pid) {
+      match parents.get(&pid) {
+        Some(&ppid) => pid = ppid,
+        None => break,
+      }
+    }
+  }
+  processes.retain(|process| keep.contains(&process.pid));
+}
+
+// Signal every matching process after showing what will be hit; the exit
+// code follows pkill: 0 if something was signalled, 1 if nothing was
+fn kill_matching(
+  system: &SystemInfo,
+  filters: &Filters,
+  signal: i32,
+  yes: bool,
+) -> io::Result<i32> {
+  if filters.is_empty() {
+    eprintln!("Error: kill needs at least one of --user, --name, --state, --ppid or --container");
+    return Ok(2);
+  }
+  // The PIDs in a copied or fake tree belong to unrelated processes on this machine
+  if system.proc_root != Path::new("/proc") {
+    eprintln!("Error: kill only works on the live /proc");
+    return Ok(2);
+  }
+
+  let own_pid = std::process::id() as i32;
+  let targets: Vec<ProcessInfo> = read_processes(system)
+    .ok_or_else(|| io::Error::other(format!("Could not read {}", system.proc_root.display())))?
+    .into_iter()
+    .filter(|process| process.pid != own_pid && filters.matches(process, system))
+    .collect();
+  if targets.is_empty() {
+    println!("No matching processes");
+    return Ok(1);
+  }
+
+  let mut table = Table::new();
+  table.add_row(row![bFg->"PID", bFy->"User", bFb->"Name", bFr->"State", bFb->"Command"]);
+  for process in &targets {
+    let command = cell_text(process, Column::Cmd, false);
+    table.add_row(row![
+      process.pid,
+      process.user,
+      process.name,
+      process.state,
+      command
+    ]);
+  }
+  table.printstd();
+
+  let name = kill::signal_name(signal);
+  if !yes {
+    print!("Send {} to {} processes? [y/N] ", name, targets.len());
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+      println!("Nothing sent");
+      return Ok(1);
+    }
+  }
+
+  let mut sent = 0;
+  for process in &targets {
+    // The answer may have taken long enough for a process to exit and its PID to be reused
+    if !kill::is_same_process(&system.proc_root, process) {
+      eprintln!(
+        "Skipping {} ({}): it exited, or the PID now belongs to another process",
+        process.pid, process.name
+      );
+      continue;
+    }
+    match kill::send(process.pid, signal) {
+      Ok(()) => sent += 1,
+      Err(e) => eprintln!(
+        "Error: Could not signal {} ({}): {}",
+        process.pid, process.name, e
+      ),
+    }
+  }
+  println!("Sent {} to {} of {} processes", name, sent, targets.len());
+  Ok(if sent > 0 { 0 } else { 1 })
+}
+
+// The descriptors of one process; sockets also show the connection they belong to
+fn list_files(proc_root: &Path, pid: i32) -> io::Result<()> {
+  let fds = files::read_fds(proc_root, pid)?;
+  let sockets: HashMap<u64, Socket> = net::read_sockets(proc_root)
+    .into_iter()
+    .map(|socket| (socket.inode, socket))
+    .collect();
+
+  let mut table = Table::new();
+  table.add_row(row![bFg->"FD", bFb->"Type", b->"Access", bFc->"Target"]);
+  for fd in &fds {
+    let target = match net::socket_inode(&fd.target).and_then(|inode| sockets.get(&inode)) {
+      Some(socket) => format!("{} ({})", fd.target, describe_socket(socket)),
+      None => fd.target.clone(),
+    };
+    table.add_row(row![fd.fd, fd.kind, fd.access, target]);
+  }
+  table.printstd();
+  Ok(())
+}
+
+// Every socket with the processes that have it open, like `ss -p` or `lsof -i`
+fn list_sockets(proc_root: &Path, port: Option<u16>) {
+  let owners = net::socket_owners(proc_root);
+
+  let mut table = Table::new();
+  table.add_row(row![
+    bFb->"Proto", b->"Local", b->"Remote", bFr->"State", bFg->"PID", bFb->"Process"
+  ]);
+  for socket in net::read_sockets(proc_root) {
+    if port.is_some() && socket.port != port {
+      continue;
+    }
+    let holders = owners.get(&socket.inode);
+    let pids = holders.map_or(String::new(), |holders| {
+      holders
+        .iter()
+        .map(|(pid, _)| pid.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+    });
+    let names = holders.map_or(String::new(), |holders| {
+      holders
+        .iter()
+        .map(|(_, name)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+    });
+    table.add_row(row![
+      socket.protocol,
+      socket.local,
+      socket.remote,
+      socket.state,
+      pids,
+      names
+    ]);
+  }
+  table.printstd();
+}
+
+fn describe_socket(socket: &Socket) -> String {
+  if socket.port.is_some() {
+    format!(
+      "{} {} -> {} {}",
+      socket.protocol, socket.local, socket.remote, socket.state
+    )
   } else {
-    None // If the format is invalid, we will return None
+    format!("{} {} {}", socket.protocol, socket.local, socket.state)
   }
 }
+
+// Command lines longer than this are cut unless --wide is given
+const COMMAND_WIDTH: usize = 80;
+
+// The text shown for one column of a process
+fn cell_text(process: &ProcessInfo, column: Column, wide: bool) -> String {
+  match column {
+    Column::Pid => process.pid.to_string(),
+    Column::Ppid => process.ppid.to_string(),
+    Column::User => process.user.clone(),
+    Column::Name => process.name.clone(),
+    Column::State => process.state.clone(),
+    Column::Nice => process.nice.to_string(),
+    Column::Threads => process.threads.to_string(),
+    Column::Rss => process.rss.to_string(),
+    Column::Vsz => process.vsz.to_string(),
+    Column::Start => process.start_time.format("%Y-%m-%d %H:%M").to_string(),
+    Column::Cgroup => process.cgroup.clone(),
+    Column::Container => process.container.clone().unwrap_or_else(|| "-".to_string()),
+    Column::Pidns => namespace_text(process.namespaces.pid),
+    Column::Netns => namespace_text(process.namespaces.net),
+    Column::Mntns => namespace_text(process.namespaces.mnt),
+    Column::Userns => namespace_text(process.namespaces.user),
+    Column::Utsns => namespace_text(process.namespaces.uts),
+    Column::Ipcns => namespace_text(process.namespaces.ipc),
+    Column::Cgroupns => namespace_text(process.namespaces.cgroup),
+    Column::Cmd => {
+      let command = process.command();
+      if wide || command.chars().count() <= COMMAND_WIDTH {
+        command
+      } else {
+        let cut: String = command.chars().take(COMMAND_WIDTH - 3).collect();
+        format!("{}...", cut)
+      }
+    }
+  }
+}
+
+// A namespace inode, or "-" when it could not be read
+fn namespace_text(inode: Option<u64>) -> String {
+  inode.map_or_else(|| "-".to_string(), |inode| inode.to_string())
+}
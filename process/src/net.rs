@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+/// One socket from /proc/net/{tcp,tcp6,udp,udp6,unix}
+pub struct Socket {
+  pub protocol: &'static str,
+  pub local: String,
+  pub remote: String,
+  pub state: String,
+  /// Port of the local address, None for Unix sockets
+  pub port: Option<u16>,
+  /// The inode a descriptor in /proc/<pid>/fd links to as socket:[inode]
+  pub inode: u64,
+}
+
+/// Every socket the kernel lists for the current network namespace
+pub fn read_sockets(proc_root: &Path) -> Vec<Socket> {
+  let mut sockets = Vec::new();
+  for (file, protocol) in [
+    ("tcp", "tcp"),
+    ("tcp6", "tcp6"),
+    ("udp", "udp"),
+    ("udp6", "udp6"),
+  ] {
+    if let Ok(content) = fs::read_to_string(proc_root.join("net").join(file)) {
+      sockets.extend(
+        content
+          .lines()
+          .skip(1)
+          .filter_map(|l| parse_inet(l, protocol)),
+      );
+    }
+  }
+  if let Ok(content) = fs::read_to_string(proc_root.join("net/unix")) {
+    sockets.extend(content.lines().skip(1).filter_map(parse_unix));
+  }
+  sockets
+}
+
+/// The processes holding each socket inode, found by reading every /proc/<pid>/fd.
+/// Descriptors of other users' processes are only visible to root.
+pub fn socket_owners(proc_root: &Path) -> HashMap<u64, Vec<(i32, String)>> {
+  let mut owners: HashMap<u64, Vec<(i32, String)>> = HashMap::new();
+  let Ok(entries) = fs::read_dir(proc_root) else {
+    return owners;
+  };
+
+  for entry in entries.filter_map(Result::ok) {
+    let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+      continue;
+    };
+    let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+      continue;
+    };
+    let name = fs::read_to_string(entry.path().join("comm"))
+      .map(|comm| comm.trim_end().to_string())
+      .unwrap_or_default();
+
+    for fd in fds.filter_map(Result::ok) {
+      if let Ok(target) = fs::read_link(fd.path())
+        && let Some(inode) = socket_inode(&target.to_string_lossy())
+      {
+        let holders = owners.entry(inode).or_default();
+        // A process often has the same socket open more than once
+        if !holders.iter().any(|(holder, _)| *holder == pid) {
+          holders.push((pid, name.clone()));
+        }
+      }
+    }
+  }
+  owners
+}
+
+/// The inode in a descriptor target like "socket:[12345]"
+pub fn socket_inode(target: &str) -> Option<u64> {
+  target
+    .strip_prefix("socket:[")?
+    .strip_suffix(']')?
+    .parse()
+    .ok()
+}
+
+// "  0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000  0 12345 ..."
+fn parse_inet(line: &str, protocol: &'static str) -> Option<Socket> {
+  let fields: Vec<&str> = line.split_whitespace().collect();
+  let (local, port) = parse_address(fields.get(1)?)?;
+  let (remote, _) = parse_address(fields.get(2)?)?;
+  let state = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+
+  Some(Socket {
+    protocol,
+    local,
+    remote,
+    state: if protocol.starts_with("tcp") {
+      tcp_state(state).to_string()
+    } else if state == 1 {
+      "ESTABLISHED".to_string()
+    } else {
+      // UDP sockets that are not connect()ed show as TCP_CLOSE
+      "UNCONN".to_string()
+    },
+    port: Some(port),
+    inode: fields.get(9)?.parse().ok()?,
+  })
+}
+
+// The address is the raw in-kernel value printed as 32-bit words in host byte
+// order, followed by the port in ordinary hex
+fn parse_address(text: &str) -> Option<(String, u16)> {
+  let (address, port) = text.split_once(':')?;
+  let port = u16::from_str_radix(port, 16).ok()?;
+
+  let mut bytes = Vec::with_capacity(16);
+  for chunk in address.as_bytes().chunks(8) {
+    let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+    bytes.extend_from_slice(&word.to_ne_bytes());
+  }
+
+  let address = match bytes.len() {
+    4 => format!(
+      "{}:{}",
+      Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]),
+      port
+    ),
+    16 => {
+      let octets: [u8; 16] = bytes.try_into().ok()?;
+      format!("[{}]:{}", Ipv6Addr::from(octets), port)
+    }
+    _ => return None,
+  };
+  Some((address, port))
+}
+
+fn tcp_state(state: u8) -> &'static str {
+  match state {
+    0x01 => "ESTABLISHED",
+    0x02 => "SYN_SENT",
+    0x03 => "SYN_RECV",
+    0x04 => "FIN_WAIT1",
+    0x05 => "FIN_WAIT2",
+    0x06 => "TIME_WAIT",
+    0x07 => "CLOSE",
+    0x08 => "CLOSE_WAIT",
+    0x09 => "LAST_ACK",
+    0x0A => "LISTEN",
+    0x0B => "CLOSING",
+    0x0C => "NEW_SYN_RECV",
+    _ => "UNKNOWN",
+  }
+}
+
+// "Num RefCount Protocol Flags Type St Inode Path", where the path is optional
+fn parse_unix(line: &str) -> Option<Socket> {
+  // Split off the seven fixed fields one by one, as the path may contain spaces
+  let mut fields = Vec::with_capacity(7);
+  let mut rest = line.trim_start();
+  while fields.len() < 7 && !rest.is_empty() {
+    let (field, tail) = rest.split_once(' ').unwrap_or((rest, ""));
+    fields.push(field);
+    rest = tail.trim_start();
+  }
+  if fields.len() < 7 {
+    return None;
+  }
+
+  let flags = u32::from_str_radix(fields[3], 16).ok()?;
+  let protocol = match u16::from_str_radix(fields[4], 16).ok()? {
+    1 => "unix stream",
+    2 => "unix dgram",
+    5 => "unix seqpacket",
+    _ => "unix",
+  };
+  // __SO_ACCEPTCON is set on listening sockets
+  let state = if flags & 0x10000 != 0 {
+    "LISTEN"
+  } else {
+    match u8::from_str_radix(fields[5], 16).ok()? {
+      1 => "UNCONNECTED",
+      2 => "CONNECTING",
+      3 => "CONNECTED",
+      4 => "DISCONNECTING",
+      _ => "UNKNOWN",
+    }
+  };
+  // Unnamed sockets have no path; abstract ones start with '@'
+  let path = match rest.trim_end() {
+    "" => "-",
+    path => path,
+  };
+
+  Some(Socket {
+    protocol,
+    local: path.to_string(),
+    remote: "-".to_string(),
+    state: state.to_string(),
+    port: None,
+    inode: fields[6].parse().ok()?,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fixture() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc")
+  }
+
+  #[test]
+  fn sockets_from_fixture() {
+    let sockets = read_sockets(&fixture());
+    let summary: Vec<(&str, &str, &str, &str, u64)> = sockets
+      .iter()
+      .map(|s| {
+        let state = s.state.as_str();
+        (
+          s.protocol,
+          s.local.as_str(),
+          s.remote.as_str(),
+          state,
+          s.inode,
+        )
+      })
+      .collect();
+    assert_eq!(
+      summary,
+      [
+        ("tcp", "0.0.0.0:8080", "0.0.0.0:0", "LISTEN", 3003),
+        (
+          "tcp",
+          "127.0.0.1:41384",
+          "127.0.0.1:631",
+          "ESTABLISHED",
+          1001
+        ),
+        ("tcp6", "[::]:22", "[::]:0", "LISTEN", 4004),
+        ("tcp6", "[::1]:8080", "[::1]:54000", "TIME_WAIT", 0),
+        ("udp", "0.0.0.0:68", "0.0.0.0:0", "UNCONN", 5005),
+        ("unix stream", "/run/my app.sock", "-", "LISTEN", 6006),
+        ("unix stream", "-", "-", "CONNECTED", 7007),
+        ("unix dgram", "@/tmp/.X11-unix/X0", "-", "UNCONNECTED", 8008),
+      ]
+    );
+    assert_eq!(sockets[0].port, Some(8080));
+    assert_eq!(sockets[5].port, None);
+  }
+
+  #[test]
+  fn owners_from_fixture() {
+    let owners = socket_owners(&fixture());
+    let mut listener = owners[&3003].clone();
+    listener.sort();
+    assert_eq!(
+      listener,
+      [(100, "nginx".to_string()), (101, "nginx".to_string())]
+    );
+    assert_eq!(owners[&1001], [(42, "Web Content".to_string())]);
+    assert!(!owners.contains_key(&4004));
+  }
+
+  #[test]
+  fn socket_inodes() {
+    assert_eq!(socket_inode("socket:[12345]"), Some(12345));
+    assert_eq!(socket_inode("pipe:[12345]"), None);
+    assert_eq!(socket_inode("socket:[]"), None);
+  }
+}
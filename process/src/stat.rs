@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::Path;
+use std::str::{FromStr, SplitWhitespace};
+
+/// Every field of /proc/<pid>/stat, in the order and with the types of proc(5).
+/// Times are in clock ticks and addresses are raw values; fields a kernel is too
+/// old to have (45 to 52 were added in Linux 3.3 and 3.5) are left at zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stat {
+  /// (1) Process ID
+  pub pid: i32,
+  /// (2) Command name, without the surrounding parentheses
+  pub comm: String,
+  /// (3) State: R, S, D, Z, T, t, X, I, ...
+  pub state: char,
+  /// (4) Parent PID
+  pub ppid: i32,
+  /// (5) Process group ID
+  pub pgrp: i32,
+  /// (6) Session ID
+  pub session: i32,
+  /// (7) Controlling terminal, as a device number
+  pub tty_nr: i32,
+  /// (8) Foreground process group of the controlling terminal
+  pub tpgid: i32,
+  /// (9) Kernel flags word (PF_*)
+  pub flags: u32,
+  /// (10) Minor faults
+  pub minflt: u64,
+  /// (11) Minor faults of waited-for children
+  pub cminflt: u64,
+  /// (12) Major faults
+  pub majflt: u64,
+  /// (13) Major faults of waited-for children
+  pub cmajflt: u64,
+  /// (14) Time scheduled in user mode
+  pub utime: u64,
+  /// (15) Time scheduled in kernel mode
+  pub stime: u64,
+  /// (16) User mode time of waited-for children
+  pub cutime: i64,
+  /// (17) Kernel mode time of waited-for children
+  pub cstime: i64,
+  /// (18) Scheduling priority as the kernel sees it
+  pub priority: i64,
+  /// (19) Nice value, from 19 (low priority) to -20 (high priority)
+  pub nice: i64,
+  /// (20) Number of threads
+  pub num_threads: i64,
+  /// (21) Obsolete, always 0
+  pub itrealvalue: i64,
+  /// (22) Time the process started after boot
+  pub starttime: u64,
+  /// (23) Virtual memory size in bytes
+  pub vsize: u64,
+  /// (24) Resident set size in pages
+  pub rss: i64,
+  /// (25) Soft limit on the RSS in bytes
+  pub rsslim: u64,
+  /// (26) Start of the program text
+  pub startcode: u64,
+  /// (27) End of the program text
+  pub endcode: u64,
+  /// (28) Start (bottom) of the stack
+  pub startstack: u64,
+  /// (29) Current stack pointer, only shown to privileged readers
+  pub kstkesp: u64,
+  /// (30) Current instruction pointer, only shown to privileged readers
+  pub kstkeip: u64,
+  /// (31) Obsolete bitmap of pending signals
+  pub signal: u64,
+  /// (32) Obsolete bitmap of blocked signals
+  pub blocked: u64,
+  /// (33) Obsolete bitmap of ignored signals
+  pub sigignore: u64,
+  /// (34) Obsolete bitmap of caught signals
+  pub sigcatch: u64,
+  /// (35) Non-zero if the process is waiting in the kernel
+  pub wchan: u64,
+  /// (36) Not maintained, always 0
+  pub nswap: u64,
+  /// (37) Not maintained, always 0
+  pub cnswap: u64,
+  /// (38) Signal sent to the parent when the process dies
+  pub exit_signal: i32,
+  /// (39) CPU the process last ran on
+  pub processor: i32,
+  /// (40) Real-time priority, 0 for normal processes
+  pub rt_priority: u32,
+  /// (41) Scheduling policy (SCHED_*)
+  pub policy: u32,
+  /// (42) Time spent waiting for block I/O
+  pub delayacct_blkio_ticks: u64,
+  /// (43) Time spent running a virtual CPU for a guest
+  pub guest_time: u64,
+  /// (44) Guest time of waited-for children
+  pub cguest_time: i64,
+  /// (45) Start of the initialized and uninitialized data
+  pub start_data: u64,
+  /// (46) End of the initialized and uninitialized data
+  pub end_data: u64,
+  /// (47) Start of the heap
+  pub start_brk: u64,
+  /// (48) Start of the command line arguments
+  pub arg_start: u64,
+  /// (49) End of the command line arguments
+  pub arg_end: u64,
+  /// (50) Start of the environment
+  pub env_start: u64,
+  /// (51) End of the environment
+  pub env_end: u64,
+  /// (52) Exit status as reported by waitpid
+  pub exit_code: i32,
+}
+
+impl Stat {
+  /// Read and parse /proc/<pid>/stat; None if the process does not exist (any more)
+  pub fn read(proc_root: &Path, pid: i32) -> Option<Stat> {
+    Stat::parse(&fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?)
+  }
+
+  /// Parse the contents of a stat file.
+  ///
+  /// The name may contain spaces and parentheses, so it is taken to run from the
+  /// first '(' to the last ')'; nothing after the name can contain a ')'.
+  /// Returns None if the line is cut short before field 44 or a field is not a number.
+  pub fn parse(content: &str) -> Option<Stat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    if close < open {
+      return None;
+    }
+
+    let mut fields = Fields(content[close + 1..].split_whitespace());
+    Some(Stat {
+      pid: content[..open].trim().parse().ok()?,
+      comm: content[open + 1..close].to_string(),
+      state: fields.next_char()?,
+      ppid: fields.next()?,
+      pgrp: fields.next()?,
+      session: fields.next()?,
+      tty_nr: fields.next()?,
+      tpgid: fields.next()?,
+      flags: fields.next()?,
+      minflt: fields.next()?,
+      cminflt: fields.next()?,
+      majflt: fields.next()?,
+      cmajflt: fields.next()?,
+      utime: fields.next()?,
+      stime: fields.next()?,
+      cutime: fields.next()?,
+      cstime: fields.next()?,
+      priority: fields.next()?,
+      nice: fields.next()?,
+      num_threads: fields.next()?,
+      itrealvalue: fields.next()?,
+      starttime: fields.next()?,
+      vsize: fields.next()?,
+      rss: fields.next()?,
+      rsslim: fields.next()?,
+      startcode: fields.next()?,
+      endcode: fields.next()?,
+      startstack: fields.next()?,
+      kstkesp: fields.next()?,
+      kstkeip: fields.next()?,
+      signal: fields.next()?,
+      blocked: fields.next()?,
+      sigignore: fields.next()?,
+      sigcatch: fields.next()?,
+      wchan: fields.next()?,
+      nswap: fields.next()?,
+      cnswap: fields.next()?,
+      exit_signal: fields.next()?,
+      processor: fields.next()?,
+      rt_priority: fields.next()?,
+      policy: fields.next()?,
+      delayacct_blkio_ticks: fields.next()?,
+      guest_time: fields.next()?,
+      cguest_time: fields.next()?,
+      start_data: fields.next_or_zero()?,
+      end_data: fields.next_or_zero()?,
+      start_brk: fields.next_or_zero()?,
+      arg_start: fields.next_or_zero()?,
+      arg_end: fields.next_or_zero()?,
+      env_start: fields.next_or_zero()?,
+      env_end: fields.next_or_zero()?,
+      exit_code: fields.next_or_zero()?,
+    })
+  }
+}
+
+// The fields after the name, consumed in order
+struct Fields<'a>(SplitWhitespace<'a>);
+
+impl Fields<'_> {
+  fn next<T: FromStr>(&mut self) -> Option<T> {
+    self.0.next()?.parse().ok()
+  }
+
+  fn next_char(&mut self) -> Option<char> {
+    let mut chars = self.0.next()?.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+  }
+
+  // For fields newer kernels added at the end: missing is zero, garbage is still an error
+  fn next_or_zero<T: FromStr + Default>(&mut self) -> Option<T> {
+    match self.0.next() {
+      Some(field) => field.parse().ok(),
+      None => Some(T::default()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fixture(name: &str) -> Stat {
+    let path = format!(
+      "{}/tests/fixtures/stat/{}",
+      env!("CARGO_MANIFEST_DIR"),
+      name
+    );
+    let content = std::fs::read_to_string(&path).unwrap();
+    Stat::parse(&content).unwrap_or_else(|| panic!("{} did not parse", path))
+  }
+
+  #[test]
+  fn name_with_a_space() {
+    let stat = fixture("web_content");
+    assert_eq!(stat.pid, 12987);
+    assert_eq!(stat.comm, "Web Content");
+    assert_eq!(stat.state, 'S');
+    assert_eq!(stat.ppid, 12943);
+    assert_eq!(stat.num_threads, 1);
+    assert_eq!(stat.starttime, 125169);
+    assert_eq!(stat.vsize, 16969728);
+    assert_eq!(stat.rss, 2584);
+    assert_eq!(stat.rsslim, u64::MAX);
+    assert_eq!(stat.exit_signal, 17);
+    assert_eq!(stat.env_end, 140727943901135);
+    assert_eq!(stat.exit_code, 0);
+  }
+
+  #[test]
+  fn names_with_parentheses() {
+    for (file, comm) in [
+      ("paren_space", "a) b"),
+      ("parens", ") ("),
+      ("nested_parens", "x)(y) z)"),
+    ] {
+      let stat = fixture(file);
+      assert_eq!(stat.comm, comm);
+      // Everything after the name must line up the same as for a plain name
+      assert_eq!(stat.state, 'S');
+      assert_eq!(stat.ppid, 12943);
+      assert_eq!(stat.pgrp, 12943);
+      assert_eq!(stat.session, 12938);
+      assert_eq!(stat.tpgid, -1);
+      assert_eq!(stat.flags, 4194368);
+      assert_eq!(stat.priority, 20);
+      assert_eq!(stat.start_data, 94411247922608);
+    }
+  }
+
+  #[test]
+  fn kernel_thread() {
+    let stat = fixture("kernel_thread");
+    assert_eq!(stat.pid, 2);
+    assert_eq!(stat.comm, "kthreadd");
+    assert_eq!(stat.ppid, 0);
+    assert_eq!(stat.vsize, 0);
+    assert_eq!(stat.sigignore, 2147483647);
+  }
+
+  #[test]
+  fn old_kernel_without_the_newer_fields() {
+    let stat = fixture("old_kernel");
+    assert_eq!(stat.comm, "bash");
+    assert_eq!(stat.cguest_time, 0);
+    assert_eq!(stat.start_data, 0);
+    assert_eq!(stat.exit_code, 0);
+  }
+
+  #[test]
+  fn rejects_broken_lines() {
+    let content = std::fs::read_to_string(format!(
+      "{}/tests/fixtures/stat/truncated",
+      env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    assert_eq!(Stat::parse(&content), None);
+    assert_eq!(Stat::parse(""), None);
+    assert_eq!(Stat::parse("12 sshd S 1"), None);
+    assert_eq!(Stat::parse("12 )sshd( S 1"), None);
+    assert_eq!(Stat::parse("x (sshd) S 1"), None);
+  }
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::Path;
+
+/// Resource usage of one process at one moment, from /proc/<pid>/{io,status,schedstat,smaps_rollup}.
+/// Every value is None when its file could not be read: io and smaps_rollup
+/// need the rights of the owner (or root), and schedstat needs CONFIG_SCHEDSTATS.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProcessStats {
+  /// Bytes passed to read() and write(), including cached and terminal I/O
+  pub rchar: Option<u64>,
+  pub wchar: Option<u64>,
+  /// Number of read and write system calls
+  pub syscr: Option<u64>,
+  pub syscw: Option<u64>,
+  /// Bytes actually fetched from and sent to the storage layer
+  pub read_bytes: Option<u64>,
+  pub write_bytes: Option<u64>,
+  /// Resident and swapped out memory in KiB
+  pub vm_rss: Option<u64>,
+  pub vm_swap: Option<u64>,
+  pub voluntary_switches: Option<u64>,
+  pub nonvoluntary_switches: Option<u64>,
+  /// Time spent on a CPU and waiting on a run queue, in nanoseconds
+  pub run_time: Option<u64>,
+  pub wait_time: Option<u64>,
+  pub timeslices: Option<u64>,
+  /// Proportional set size in KiB: private pages plus a fair share of shared ones
+  pub pss: Option<u64>,
+  /// Shared and private pages in KiB, clean and dirty added together
+  pub shared: Option<u64>,
+  pub private: Option<u64>,
+}
+
+impl ProcessStats {
+  /// Read the statistics of a process; None if it does not exist (any more)
+  pub fn read(proc_root: &Path, pid: i32) -> Option<ProcessStats> {
+    let proc_path = proc_root.join(pid.to_string());
+    // status is readable for every process, so it tells whether the PID exists
+    let status = fs::read_to_string(proc_path.join("status")).ok()?;
+    let io = fs::read_to_string(proc_path.join("io")).unwrap_or_default();
+    let smaps = fs::read_to_string(proc_path.join("smaps_rollup")).unwrap_or_default();
+    let schedstat = fs::read_to_string(proc_path.join("schedstat")).unwrap_or_default();
+    let sched: Vec<u64> = schedstat
+      .split_whitespace()
+      .filter_map(|value| value.parse().ok())
+      .collect();
+
+    let sum = |a: Option<u64>, b: Option<u64>| Some(a? + b?);
+    Some(ProcessStats {
+      rchar: value(&io, "rchar:"),
+      wchar: value(&io, "wchar:"),
+      syscr: value(&io, "syscr:"),
+      syscw: value(&io, "syscw:"),
+      read_bytes: value(&io, "read_bytes:"),
+      write_bytes: value(&io, "write_bytes:"),
+      vm_rss: value(&status, "VmRSS:"),
+      vm_swap: value(&status, "VmSwap:"),
+      voluntary_switches: value(&status, "voluntary_ctxt_switches:"),
+      nonvoluntary_switches: value(&status, "nonvoluntary_ctxt_switches:"),
+      run_time: sched.first().copied(),
+      wait_time: sched.get(1).copied(),
+      timeslices: sched.get(2).copied(),
+      pss: value(&smaps, "Pss:"),
+      shared: sum(
+        value(&smaps, "Shared_Clean:"),
+        value(&smaps, "Shared_Dirty:"),
+      ),
+      private: sum(
+        value(&smaps, "Private_Clean:"),
+        value(&smaps, "Private_Dirty:"),
+      ),
+    })
+  }
+
+  /// Every value with its name and unit, in a fixed order for tables and CSV columns
+  pub fn fields(&self) -> [(&'static str, &'static str, Option<u64>); 16] {
+    [
+      ("rchar", "bytes", self.rchar),
+      ("wchar", "bytes", self.wchar),
+      ("syscr", "calls", self.syscr),
+      ("syscw", "calls", self.syscw),
+      ("read_bytes", "bytes", self.read_bytes),
+      ("write_bytes", "bytes", self.write_bytes),
+      ("vm_rss", "KiB", self.vm_rss),
+      ("vm_swap", "KiB", self.vm_swap),
+      ("voluntary_switches", "switches", self.voluntary_switches),
+      (
+        "nonvoluntary_switches",
+        "switches",
+        self.nonvoluntary_switches,
+      ),
+      ("run_time", "ns", self.run_time),
+      ("wait_time", "ns", self.wait_time),
+      ("timeslices", "slices", self.timeslices),
+      ("pss", "KiB", self.pss),
+      ("shared", "KiB", self.shared),
+      ("private", "KiB", self.private),
+    ]
+  }
+
+  /// The CSV header matching `csv_row`
+  pub fn csv_header() -> String {
+    let names: Vec<&str> = ProcessStats::default()
+      .fields()
+      .iter()
+      .map(|&(name, _, _)| name)
+      .collect();
+    format!("timestamp,{}", names.join(","))
+  }
+
+  /// One CSV line; values that could not be read are left empty
+  pub fn csv_row(&self, timestamp: &str) -> String {
+    let values: Vec<String> = self
+      .fields()
+      .iter()
+      .map(|&(_, _, value)| value.map_or_else(String::new, |v| v.to_string()))
+      .collect();
+    format!("{},{}", timestamp, values.join(","))
+  }
+}
+
+// The number after "key:" on its own line, ignoring a trailing unit like "kB"
+fn value(content: &str, key: &str) -> Option<u64> {
+  content
+    .lines()
+    .find_map(|line| line.strip_prefix(key))?
+    .split_whitespace()
+    .next()?
+    .parse()
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fixture() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc")
+  }
+
+  #[test]
+  fn reads_every_source() {
+    let stats = ProcessStats::read(&fixture(), 42).unwrap();
+    assert_eq!(stats.rchar, Some(1048576));
+    assert_eq!(stats.write_bytes, Some(8192));
+    assert_eq!(stats.vm_rss, Some(204800));
+    assert_eq!(stats.vm_swap, Some(1024));
+    assert_eq!(stats.voluntary_switches, Some(15000));
+    assert_eq!(stats.nonvoluntary_switches, Some(320));
+    assert_eq!(stats.run_time, Some(45000000000));
+    assert_eq!(stats.wait_time, Some(1200000000));
+    assert_eq!(stats.timeslices, Some(9000));
+    assert_eq!(stats.pss, Some(150000));
+    assert_eq!(stats.shared, Some(60000 + 200));
+    assert_eq!(stats.private, Some(4000 + 140600));
+  }
+
+  #[test]
+  fn unreadable_files_are_left_empty() {
+    // PID 1 in the fixture only has a status file without memory lines
+    let stats = ProcessStats::read(&fixture(), 1).unwrap();
+    assert_eq!(stats, ProcessStats::default());
+    assert!(ProcessStats::read(&fixture(), 99999).is_none());
+  }
+
+  #[test]
+  fn csv_columns_line_up() {
+    let stats = ProcessStats::read(&fixture(), 42).unwrap();
+    let header = ProcessStats::csv_header();
+    let row = stats.csv_row("2024-01-01T00:00:00");
+    assert!(header.starts_with("timestamp,rchar,wchar,"));
+    assert_eq!(header.split(',').count(), row.split(',').count());
+    assert!(row.starts_with("2024-01-01T00:00:00,1048576,"));
+    assert_eq!(
+      ProcessStats::default().csv_row("t"),
+      format!("t{}", ",".repeat(16))
+    );
+  }
+}
@@ -0,0 +1,353 @@
+use crossterm::{
+  cursor,
+  event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+  execute, queue,
+  style::{Attribute, Print, SetAttribute},
+  terminal::{self, ClearType},
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::info::{ProcessInfo, SystemInfo, read_processes};
+
+/// What the rows are ordered by
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+  Cpu,
+  Memory,
+  Pid,
+  Name,
+}
+
+/// One line of the live view
+struct TopRow {
+  pid: i32,
+  user: String,
+  state: String,
+  cpu: f64,
+  memory: f64,
+  rss: u64,
+  name: String,
+  command: String,
+}
+
+/// Turns cumulative CPU times into percentages between two samples
+struct CpuSampler {
+  previous_total: u64,
+  previous_idle: u64,
+  previous: HashMap<i32, u64>,
+  cpus: u64,
+}
+
+struct TopState {
+  sort: SortKey,
+  reverse: bool,
+  paused: bool,
+  filter: String,
+  // The search text being typed after '/', if any
+  input: Option<String>,
+  rows: Vec<TopRow>,
+  // Total CPU usage of the system and memory in use, as percentages
+  system_cpu: f64,
+  system_memory: f64,
+}
+
+/// Run the live view until the user quits with 'q'
+pub fn run(system: &SystemInfo, delay: Duration) -> io::Result<()> {
+  let mut stdout = io::stdout();
+  terminal::enable_raw_mode()?;
+  execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+  let result = event_loop(system, delay);
+
+  // Always give the terminal back, even if drawing failed
+  execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+  terminal::disable_raw_mode()?;
+  result
+}
+
+fn event_loop(system: &SystemInfo, delay: Duration) -> io::Result<()> {
+  let mut sampler = CpuSampler::new();
+  let mut state = TopState {
+    sort: SortKey::Cpu,
+    reverse: false,
+    paused: false,
+    filter: String::new(),
+    input: None,
+    rows: Vec::new(),
+    system_cpu: 0.0,
+    system_memory: 0.0,
+  };
+
+  // The first sample only establishes a baseline, so take the second one soon after
+  sampler.sample(
+    &read_processes(system).unwrap_or_default(),
+    &system.proc_root,
+  );
+  let mut next_sample = Instant::now() + Duration::from_millis(250);
+
+  loop {
+    if !state.paused && Instant::now() >= next_sample {
+      refresh(&mut state, &mut sampler, system);
+      next_sample = Instant::now() + delay;
+    }
+    draw(&state)?;
+
+    let timeout = if state.paused {
+      delay
+    } else {
+      next_sample.saturating_duration_since(Instant::now())
+    };
+    if event::poll(timeout)?
+      && let Event::Key(key) = event::read()?
+      && key.kind == KeyEventKind::Press
+      && !handle_key(&mut state, key)
+    {
+      return Ok(());
+    }
+  }
+}
+
+// Update the state for a key press; returns false when the user quits
+fn handle_key(state: &mut TopState, key: KeyEvent) -> bool {
+  if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+    return false;
+  }
+
+  // While searching, keys edit the search text
+  if let Some(input) = &mut state.input {
+    match key.code {
+      KeyCode::Char(c) => input.push(c),
+      KeyCode::Backspace => {
+        input.pop();
+      }
+      KeyCode::Enter => state.filter = state.input.take().unwrap_or_default(),
+      KeyCode::Esc => state.input = None,
+      _ => {}
+    }
+    return true;
+  }
+
+  match key.code {
+    KeyCode::Char('q') | KeyCode::Esc => return false,
+    KeyCode::Char(' ') | KeyCode::Char('p') => state.paused = !state.paused,
+    KeyCode::Char('c') => state.sort = SortKey::Cpu,
+    KeyCode::Char('m') => state.sort = SortKey::Memory,
+    KeyCode::Char('i') => state.sort = SortKey::Pid,
+    KeyCode::Char('n') => state.sort = SortKey::Name,
+    KeyCode::Char('r') => state.reverse = !state.reverse,
+    KeyCode::Char('/') => state.input = Some(state.filter.clone()),
+    _ => {}
+  }
+  true
+}
+
+fn refresh(state: &mut TopState, sampler: &mut CpuSampler, system: &SystemInfo) {
+  let processes = read_processes(system).unwrap_or_default();
+  let (cpu, system_cpu) = sampler.sample(&processes, &system.proc_root);
+  let (mem_total, mem_available) = read_memory(&system.proc_root);
+
+  state.system_cpu = system_cpu;
+  state.system_memory = if mem_total > 0 {
+    (mem_total - mem_available.min(mem_total)) as f64 * 100.0 / mem_total as f64
+  } else {
+    0.0
+  };
+  state.rows = processes
+    .into_iter()
+    .map(|process| TopRow {
+      cpu: cpu.get(&process.pid).copied().unwrap_or(0.0),
+      memory: if mem_total > 0 {
+        process.rss as f64 * 100.0 / mem_total as f64
+      } else {
+        0.0
+      },
+      command: process.command(),
+      pid: process.pid,
+      user: process.user,
+      state: process.state,
+      rss: process.rss,
+      name: process.name,
+    })
+    .collect();
+}
+
+fn draw(state: &TopState) -> io::Result<()> {
+  let (width, height) = terminal::size()?;
+  let width = width as usize;
+  let mut stdout = io::stdout();
+
+  let mut rows: Vec<&TopRow> = state
+    .rows
+    .iter()
+    .filter(|row| matches_filter(row, &state.filter))
+    .collect();
+  rows.sort_by(|a, b| {
+    let order = match state.sort {
+      SortKey::Cpu => b.cpu.total_cmp(&a.cpu),
+      SortKey::Memory => b.rss.cmp(&a.rss),
+      SortKey::Pid => a.pid.cmp(&b.pid),
+      SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    };
+    if state.reverse {
+      order.reverse()
+    } else {
+      order
+    }
+  });
+
+  let summary = format!(
+    "process top - {}  tasks: {}  cpu: {:.1}%  mem: {:.1}%{}",
+    chrono::Local::now().format("%H:%M:%S"),
+    state.rows.len(),
+    state.system_cpu,
+    state.system_memory,
+    if state.paused { "  [PAUSED]" } else { "" }
+  );
+  let help = match &state.input {
+    Some(input) => format!("Search: {}_  (Enter apply, Esc cancel)", input),
+    None if !state.filter.is_empty() => format!(
+      "Filter: {}  |  sort (c)pu (m)em p(i)d (n)ame (r)everse  / search  space pause  q quit",
+      state.filter
+    ),
+    None => "sort (c)pu (m)em p(i)d (n)ame (r)everse  / search  space pause  q quit".to_string(),
+  };
+  let header = format!(
+    "{:>7} {:<10} {:<1} {:>6} {:>6} {:>10}  {}",
+    "PID", "USER", "S", "CPU%", "MEM%", "RSS(KiB)", "COMMAND"
+  );
+
+  queue!(
+    stdout,
+    terminal::Clear(ClearType::All),
+    cursor::MoveTo(0, 0),
+    Print(fit(&summary, width)),
+    cursor::MoveTo(0, 1),
+    Print(fit(&help, width)),
+    cursor::MoveTo(0, 3),
+    SetAttribute(Attribute::Reverse),
+    Print(format!("{:<width$}", fit(&header, width), width = width)),
+    SetAttribute(Attribute::Reset),
+  )?;
+
+  let visible = (height as usize).saturating_sub(4);
+  for (line, row) in rows.iter().take(visible).enumerate() {
+    let text = format!(
+      "{:>7} {:<10} {:<1} {:>6.1} {:>6.1} {:>10}  {}",
+      row.pid,
+      fit(&row.user, 10),
+      row.state,
+      row.cpu,
+      row.memory,
+      row.rss,
+      row.command
+    );
+    queue!(
+      stdout,
+      cursor::MoveTo(0, 4 + line as u16),
+      Print(fit(&text, width))
+    )?;
+  }
+  stdout.flush()
+}
+
+fn matches_filter(row: &TopRow, filter: &str) -> bool {
+  if filter.is_empty() {
+    return true;
+  }
+  let filter = filter.to_lowercase();
+  row.name.to_lowercase().contains(&filter) || row.command.to_lowercase().contains(&filter)
+}
+
+// Cut text to the terminal width
+fn fit(text: &str, width: usize) -> String {
+  text.chars().take(width).collect()
+}
+
+impl CpuSampler {
+  fn new() -> CpuSampler {
+    CpuSampler {
+      previous_total: 0,
+      previous_idle: 0,
+      previous: HashMap::new(),
+      cpus: 1,
+    }
+  }
+
+  /// CPU usage per PID since the previous sample, where 100% is one full core,
+  /// and the usage of the whole system where 100% is every core busy.
+  fn sample(&mut self, processes: &[ProcessInfo], proc_root: &Path) -> (HashMap<i32, f64>, f64) {
+    let (total, idle, cpus) = read_cpu_times(proc_root);
+    let elapsed = total.saturating_sub(self.previous_total);
+    let idle_elapsed = idle.saturating_sub(self.previous_idle);
+    self.cpus = cpus.max(1);
+
+    // Jiffies of a single core over the interval
+    let per_core = elapsed as f64 / self.cpus as f64;
+    let mut usage = HashMap::new();
+    let mut current = HashMap::new();
+    for process in processes {
+      let used = process.utime + process.stime;
+      if let Some(&before) = self.previous.get(&process.pid)
+        && per_core > 0.0
+      {
+        usage.insert(
+          process.pid,
+          used.saturating_sub(before) as f64 * 100.0 / per_core,
+        );
+      }
+      current.insert(process.pid, used);
+    }
+
+    let system = if elapsed > 0 {
+      (elapsed - idle_elapsed.min(elapsed)) as f64 * 100.0 / elapsed as f64
+    } else {
+      0.0
+    };
+
+    self.previous = current;
+    self.previous_total = total;
+    self.previous_idle = idle;
+    (usage, system)
+  }
+}
+
+// Total and idle jiffies from the "cpu" line of /proc/stat, and the number of CPUs
+fn read_cpu_times(proc_root: &Path) -> (u64, u64, u64) {
+  let stat = fs::read_to_string(proc_root.join("stat")).unwrap_or_default();
+  let mut total = 0;
+  let mut idle = 0;
+  let mut cpus = 0;
+
+  for line in stat.lines() {
+    let mut fields = line.split_whitespace();
+    match fields.next() {
+      Some("cpu") => {
+        // user nice system idle iowait irq softirq steal; guest time is already in user
+        let values: Vec<u64> = fields.take(8).filter_map(|v| v.parse().ok()).collect();
+        total = values.iter().sum();
+        idle = values.get(3).copied().unwrap_or(0) + values.get(4).copied().unwrap_or(0);
+      }
+      Some(name) if name.starts_with("cpu") => cpus += 1,
+      _ => {}
+    }
+  }
+  (total, idle, cpus)
+}
+
+// MemTotal and MemAvailable from /proc/meminfo, in KiB
+fn read_memory(proc_root: &Path) -> (u64, u64) {
+  let meminfo = fs::read_to_string(proc_root.join("meminfo")).unwrap_or_default();
+  let value = |key: &str| {
+    meminfo
+      .lines()
+      .find_map(|line| line.strip_prefix(key))
+      .and_then(|rest| rest.split_whitespace().next())
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(0)
+  };
+  (value("MemTotal:"), value("MemAvailable:"))
+}
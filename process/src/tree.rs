@@ -0,0 +1,178 @@
+use crossterm::style::Stylize;
+use std::collections::{HashMap, HashSet};
+
+use crate::info::ProcessInfo;
+
+/// How the tree is printed
+pub struct TreeOptions {
+  /// Show the PID after each name; identical siblings are then never collapsed
+  pub pids: bool,
+  /// A PID whose ancestors, down from the root, are shown in bold
+  pub highlight: Option<i32>,
+}
+
+/// A process and its children, built from the PPID of every process
+struct Node<'a> {
+  process: &'a ProcessInfo,
+  children: Vec<Node<'a>>,
+}
+
+/// Render the processes as an indented tree like pstree.
+/// Processes whose parent is not in the list (PID 1, kthreadd) become roots.
+pub fn render(processes: &[ProcessInfo], options: &TreeOptions) -> String {
+  let pids: HashSet<i32> = processes.iter().map(|process| process.pid).collect();
+  let mut children: HashMap<i32, Vec<&ProcessInfo>> = HashMap::new();
+  let mut roots = Vec::new();
+  for process in processes {
+    if process.ppid != process.pid && pids.contains(&process.ppid) {
+      children.entry(process.ppid).or_default().push(process);
+    } else {
+      roots.push(process);
+    }
+  }
+
+  let ancestry = ancestry(processes, options.highlight);
+  let mut lines = Vec::new();
+  for root in roots {
+    let node = build(root, &children);
+    let label = label(&node, 1, options, &ancestry);
+    lines.push(label);
+    render_children(&node.children, "", options, &ancestry, &mut lines);
+  }
+  lines.join("\n")
+}
+
+fn build<'a>(process: &'a ProcessInfo, children: &HashMap<i32, Vec<&'a ProcessInfo>>) -> Node<'a> {
+  let mut nodes: Vec<Node> = children
+    .get(&process.pid)
+    .map(|list| list.iter().map(|child| build(child, children)).collect())
+    .unwrap_or_default();
+  // Sorted by name so identical siblings end up next to each other
+  nodes.sort_by(|a, b| {
+    (a.process.name.as_str(), a.process.pid).cmp(&(b.process.name.as_str(), b.process.pid))
+  });
+  Node {
+    process,
+    children: nodes,
+  }
+}
+
+// The highlighted PID and all of its ancestors
+fn ancestry(processes: &[ProcessInfo], highlight: Option<i32>) -> HashSet<i32> {
+  let parents: HashMap<i32, i32> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+  let mut ancestry = HashSet::new();
+  let mut current = highlight;
+  while let Some(pid) = current {
+    // Stop at the root, or if the PPIDs somehow form a loop
+    if !parents.contains_key(&pid) || !ancestry.insert(pid) {
+      break;
+    }
+    current = parents.get(&pid).copied();
+  }
+  ancestry
+}
+
+fn render_children(
+  children: &[Node],
+  prefix: &str,
+  options: &TreeOptions,
+  ancestry: &HashSet<i32>,
+  lines: &mut Vec<String>,
+) {
+  let groups = group_identical(children, options, ancestry);
+  for (index, (node, count)) in groups.iter().enumerate() {
+    let last = index + 1 == groups.len();
+    let branch = if last { "└─ " } else { "├─ " };
+    lines.push(format!(
+      "{}{}{}",
+      prefix,
+      branch,
+      label(node, *count, options, ancestry)
+    ));
+
+    let child_prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+    render_children(&node.children, &child_prefix, options, ancestry, lines);
+  }
+}
+
+// Runs of siblings with the same subtree shape become one entry with a count
+fn group_identical<'n, 'a>(
+  children: &'n [Node<'a>],
+  options: &TreeOptions,
+  ancestry: &HashSet<i32>,
+) -> Vec<(&'n Node<'a>, usize)> {
+  let mut groups: Vec<(&Node, usize)> = Vec::new();
+  for node in children {
+    let collapsible = !options.pids && !ancestry.contains(&node.process.pid);
+    if let Some((first, count)) = groups.last_mut()
+      && collapsible
+      && !ancestry.contains(&first.process.pid)
+      && shape(first) == shape(node)
+    {
+      *count += 1;
+      continue;
+    }
+    groups.push((node, 1));
+  }
+  groups
+}
+
+// The names in a subtree, used to tell whether two siblings look the same
+fn shape(node: &Node) -> String {
+  let children: Vec<String> = node.children.iter().map(shape).collect();
+  format!("{}({})", node.process.name, children.join(","))
+}
+
+fn label(node: &Node, count: usize, options: &TreeOptions, ancestry: &HashSet<i32>) -> String {
+  let name = if options.pids {
+    format!("{}({})", node.process.name, node.process.pid)
+  } else if count > 1 {
+    format!("{}*[{}]", count, node.process.name)
+  } else {
+    node.process.name.clone()
+  };
+
+  if ancestry.contains(&node.process.pid) {
+    name.bold().to_string()
+  } else {
+    name
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::info::{read_processes, tests::fixture_system};
+
+  #[test]
+  fn collapses_identical_siblings() {
+    let processes = read_processes(&fixture_system()).unwrap();
+    let options = TreeOptions {
+      pids: false,
+      highlight: None,
+    };
+    assert_eq!(
+      render(&processes, &options),
+      "systemd\n├─ Web Content\n└─ nginx\n   └─ 2*[nginx]\nkthreadd\n└─ kworker/0:1-events"
+    );
+  }
+
+  #[test]
+  fn pids_and_highlight_keep_siblings_apart() {
+    let processes = read_processes(&fixture_system()).unwrap();
+    let options = TreeOptions {
+      pids: true,
+      highlight: None,
+    };
+    assert!(render(&processes, &options).contains("   ├─ nginx(101)\n   └─ nginx(102)"));
+
+    // The highlighted worker is split out of the group, its parents are bold
+    let options = TreeOptions {
+      pids: false,
+      highlight: Some(102),
+    };
+    let tree = render(&processes, &options);
+    assert!(tree.contains(&format!("└─ {}", "nginx".bold())));
+    assert!(tree.contains(&format!("   ├─ nginx\n   └─ {}", "nginx".bold())));
+  }
+}
@@ -0,0 +1,472 @@
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::thread;
+use std::time::Duration;
+
+use crate::info::{ProcessInfo, SystemInfo, get_process_info, read_processes};
+
+// From <linux/connector.h> and <linux/cn_proc.h>
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+const NLMSG_HEADER: usize = 16;
+const CN_MSG_HEADER: usize = 20;
+
+/// Something that happened to a process
+enum Event {
+  Fork {
+    parent: i32,
+    child: i32,
+  },
+  Exec {
+    pid: i32,
+  },
+  /// The raw wait status, None when polling cannot know it
+  Exit {
+    pid: i32,
+    status: Option<u32>,
+  },
+  /// Seen for the first time while polling, so either forked or exec'd
+  Start {
+    pid: i32,
+  },
+}
+
+/// Print fork, exec and exit events as they happen. Uses the netlink process
+/// connector, which needs CAP_NET_ADMIN, and otherwise polls the proc root.
+pub fn run(system: &SystemInfo, interval: Duration, poll: bool) -> io::Result<()> {
+  let mut printer = Printer::new(system);
+
+  if !poll {
+    match Connector::open() {
+      Ok(connector) => {
+        printer.header();
+        let e = listen(&connector, &mut printer);
+        eprintln!(
+          "Process connector failed ({}), polling every {:.1}s instead",
+          e,
+          interval.as_secs_f64()
+        );
+        return poll_events(system, interval, &mut printer);
+      }
+      Err(e) => eprintln!(
+        "Process connector not available ({}), polling every {:.1}s instead",
+        e,
+        interval.as_secs_f64()
+      ),
+    }
+  }
+
+  printer.header();
+  poll_events(system, interval, &mut printer)
+}
+
+// Print connector events until receiving fails for good. When the socket
+// buffer overflows under heavy forking the kernel drops events and reports
+// ENOBUFS once; the connector still works afterwards.
+fn listen(connector: &Connector, printer: &mut Printer) -> io::Error {
+  loop {
+    match connector.receive() {
+      Ok(events) => {
+        for event in events {
+          printer.print(event);
+        }
+      }
+      Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+        eprintln!("Events lost, the kernel produced them faster than they were read")
+      }
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+      Err(e) => return e,
+    }
+  }
+}
+
+// Compare snapshots of the proc root; short-lived processes between two
+// snapshots are missed, which is why the connector is preferred
+fn poll_events(system: &SystemInfo, interval: Duration, printer: &mut Printer) -> io::Result<()> {
+  let mut previous = snapshot(system)?;
+  loop {
+    thread::sleep(interval);
+    let current = snapshot(system)?;
+
+    for (pid, (started, command)) in &current {
+      match previous.get(pid) {
+        // A reused PID is a new process
+        Some((before, _)) if before != started => {
+          printer.print(Event::Exit {
+            pid: *pid,
+            status: None,
+          });
+          printer.print(Event::Start { pid: *pid });
+        }
+        Some((_, before)) if before != command => printer.print(Event::Exec { pid: *pid }),
+        Some(_) => {}
+        None => printer.print(Event::Start { pid: *pid }),
+      }
+    }
+    for pid in previous.keys() {
+      if !current.contains_key(pid) {
+        printer.print(Event::Exit {
+          pid: *pid,
+          status: None,
+        });
+      }
+    }
+    previous = current;
+  }
+}
+
+// Start time and command line of every process, by PID. The command line and
+// not the name is compared, as kernel threads rename themselves all the time.
+fn snapshot(system: &SystemInfo) -> io::Result<HashMap<i32, (DateTime<Local>, String)>> {
+  let processes = read_processes(system)
+    .ok_or_else(|| io::Error::other(format!("Could not read {}", system.proc_root.display())))?;
+  Ok(
+    processes
+      .into_iter()
+      .map(|process| (process.pid, (process.start_time, process.cmdline.join(" "))))
+      .collect(),
+  )
+}
+
+/// Prints events, remembering the parent and command of every process so
+/// exits can still be described after /proc/<pid> is gone
+struct Printer<'a> {
+  system: &'a SystemInfo,
+  known: HashMap<i32, (i32, String)>,
+}
+
+impl<'a> Printer<'a> {
+  fn new(system: &'a SystemInfo) -> Printer<'a> {
+    let known = read_processes(system)
+      .unwrap_or_default()
+      .into_iter()
+      .map(|process| (process.pid, (process.ppid, process.command())))
+      .collect();
+    Printer { system, known }
+  }
+
+  fn header(&self) {
+    println!(
+      "{:<12}  {:<5}  {:>7}  {:>7}  DETAILS",
+      "TIME", "EVENT", "PID", "PPID"
+    );
+  }
+
+  fn print(&mut self, event: Event) {
+    let (name, pid, ppid, details) = match event {
+      Event::Fork { parent, child } => {
+        // The child runs the parent's program until it calls exec
+        let command = self.command_of(parent);
+        self.known.insert(child, (parent, command.clone()));
+        ("fork", child, parent, command)
+      }
+      Event::Exec { pid } | Event::Start { pid } => {
+        let (ppid, command) = match self.read(pid) {
+          Some(process) => (process.ppid, process.command()),
+          // Already gone again; keep what is known from the fork
+          None => self
+            .known
+            .get(&pid)
+            .cloned()
+            .unwrap_or((0, "?".to_string())),
+        };
+        self.known.insert(pid, (ppid, command.clone()));
+        let name = if matches!(event, Event::Exec { .. }) {
+          "exec"
+        } else {
+          "start"
+        };
+        (name, pid, ppid, command)
+      }
+      Event::Exit { pid, status } => {
+        let (ppid, command) = self.known.remove(&pid).unwrap_or((0, "?".to_string()));
+        let details = format!("{} ({})", command, describe_status(status));
+        ("exit", pid, ppid, details)
+      }
+    };
+
+    println!(
+      "{:<12}  {:<5}  {:>7}  {:>7}  {}",
+      Local::now().format("%H:%M:%S%.3f"),
+      name,
+      pid,
+      ppid,
+      details
+    );
+  }
+
+  fn command_of(&self, pid: i32) -> String {
+    match self.known.get(&pid) {
+      Some((_, command)) => command.clone(),
+      None => self.read(pid).map_or("?".to_string(), |p| p.command()),
+    }
+  }
+
+  fn read(&self, pid: i32) -> Option<ProcessInfo> {
+    get_process_info(&self.system.proc_root.join(pid.to_string()), self.system)
+  }
+}
+
+// Decode a wait status the way a shell reports it
+fn describe_status(status: Option<u32>) -> String {
+  match status {
+    None => "exit code unknown".to_string(),
+    Some(status) if status & 0x7f == 0 => format!("exit code {}", (status >> 8) & 0xff),
+    Some(status) => format!("killed by signal {}", status & 0x7f),
+  }
+}
+
+/// A netlink socket subscribed to the process connector
+struct Connector {
+  socket: OwnedFd,
+}
+
+impl Connector {
+  fn open() -> io::Result<Connector> {
+    // SAFETY: plain socket creation; the descriptor is owned right away
+    let fd = unsafe {
+      libc::socket(
+        libc::AF_NETLINK,
+        libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+        libc::NETLINK_CONNECTOR,
+      )
+    };
+    if fd < 0 {
+      return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd is a freshly created, valid descriptor that nothing else owns
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // SAFETY: sockaddr_nl is plain data, so all zeroes is a valid value
+    let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_pid = 0; // let the kernel pick a port ID
+    address.nl_groups = CN_IDX_PROC;
+    // SAFETY: address is a valid sockaddr_nl and the length matches it
+    let bound = unsafe {
+      libc::bind(
+        socket.as_raw_fd(),
+        &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+        mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+      )
+    };
+    if bound < 0 {
+      return Err(io::Error::last_os_error());
+    }
+
+    // nlmsghdr, then cn_msg, then the operation
+    let length = NLMSG_HEADER + CN_MSG_HEADER + 4;
+    let mut message = Vec::with_capacity(length);
+    message.extend_from_slice(&(length as u32).to_ne_bytes());
+    message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+    message.extend_from_slice(&0u16.to_ne_bytes()); // flags
+    message.extend_from_slice(&0u32.to_ne_bytes()); // sequence
+    message.extend_from_slice(&std::process::id().to_ne_bytes());
+    message.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+    message.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes()); // sequence
+    message.extend_from_slice(&0u32.to_ne_bytes()); // ack
+    message.extend_from_slice(&4u16.to_ne_bytes()); // payload length
+    message.extend_from_slice(&0u16.to_ne_bytes()); // flags
+    message.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+
+    // SAFETY: message is a valid buffer of the given length
+    let sent = unsafe {
+      libc::send(
+        socket.as_raw_fd(),
+        message.as_ptr() as *const libc::c_void,
+        message.len(),
+        0,
+      )
+    };
+    if sent < 0 {
+      return Err(io::Error::last_os_error());
+    }
+    Ok(Connector { socket })
+  }
+
+  // Wait for the next datagram and decode the process-level events in it;
+  // thread events (where the PID is not the thread group ID) are skipped
+  fn receive(&self) -> io::Result<Vec<Event>> {
+    let mut buffer = [0u8; 4096];
+    // SAFETY: buffer is valid for writes of its full length
+    let received = unsafe {
+      libc::recv(
+        self.socket.as_raw_fd(),
+        buffer.as_mut_ptr() as *mut libc::c_void,
+        buffer.len(),
+        0,
+      )
+    };
+    if received < 0 {
+      return Err(io::Error::last_os_error());
+    }
+
+    Ok(parse_messages(&buffer[..received as usize]))
+  }
+}
+
+// Split a datagram into its netlink messages and decode the event in each
+fn parse_messages(data: &[u8]) -> Vec<Event> {
+  let mut events = Vec::new();
+  let mut offset = 0;
+  while offset + NLMSG_HEADER <= data.len() {
+    let length = read_u32(data, offset) as usize;
+    // A length shorter than the header, zero included, leaves no way to find the next message
+    if length < NLMSG_HEADER || offset + length > data.len() {
+      break;
+    }
+    // Messages too short to carry a cn_msg are not events
+    if length >= NLMSG_HEADER + CN_MSG_HEADER {
+      let event = &data[offset + NLMSG_HEADER + CN_MSG_HEADER..offset + length];
+      if let Some(event) = parse_event(event) {
+        events.push(event);
+      }
+    }
+    // Messages are padded to four bytes
+    offset += (length + 3) & !3;
+  }
+  events
+}
+
+// struct proc_event: what, cpu, timestamp_ns, then the event_data union
+fn parse_event(data: &[u8]) -> Option<Event> {
+  if data.len() < 16 {
+    return None;
+  }
+  let field = |n: usize| (data.len() >= 16 + 4 * (n + 1)).then(|| read_u32(data, 16 + 4 * n));
+
+  match read_u32(data, 0) {
+    PROC_EVENT_FORK => {
+      let (parent_tgid, child_pid, child_tgid) = (field(1)?, field(2)?, field(3)?);
+      (child_pid == child_tgid).then_some(Event::Fork {
+        parent: parent_tgid as i32,
+        child: child_pid as i32,
+      })
+    }
+    PROC_EVENT_EXEC => Some(Event::Exec {
+      pid: field(1)? as i32,
+    }),
+    PROC_EVENT_EXIT => {
+      let (pid, tgid, status) = (field(0)?, field(1)?, field(2)?);
+      (pid == tgid).then_some(Event::Exit {
+        pid: pid as i32,
+        status: Some(status),
+      })
+    }
+    _ => None,
+  }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+  u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A proc_event with the given type and event_data words
+  fn event(what: u32, words: &[u32]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&what.to_ne_bytes());
+    data.extend_from_slice(&0u32.to_ne_bytes()); // cpu
+    data.extend_from_slice(&123456789u64.to_ne_bytes()); // timestamp
+    for word in words {
+      data.extend_from_slice(&word.to_ne_bytes());
+    }
+    data
+  }
+
+  #[test]
+  fn parses_process_events() {
+    assert!(matches!(
+      parse_event(&event(PROC_EVENT_FORK, &[10, 10, 20, 20])),
+      Some(Event::Fork {
+        parent: 10,
+        child: 20
+      })
+    ));
+    assert!(matches!(
+      parse_event(&event(PROC_EVENT_EXEC, &[20, 20])),
+      Some(Event::Exec { pid: 20 })
+    ));
+    assert!(matches!(
+      parse_event(&event(PROC_EVENT_EXIT, &[20, 20, 3 << 8, 17, 10, 10])),
+      Some(Event::Exit {
+        pid: 20,
+        status: Some(0x300)
+      })
+    ));
+  }
+
+  #[test]
+  fn skips_threads_and_short_events() {
+    // A new thread 21 in process 20, and thread 21 exiting
+    assert!(parse_event(&event(PROC_EVENT_FORK, &[10, 20, 21, 20])).is_none());
+    assert!(parse_event(&event(PROC_EVENT_EXIT, &[21, 20, 0, 17])).is_none());
+    assert!(parse_event(&event(PROC_EVENT_FORK, &[10, 10])).is_none());
+    assert!(parse_event(&[0; 8]).is_none());
+  }
+
+  // A netlink message carrying a cn_msg around the event
+  fn message(event: &[u8]) -> Vec<u8> {
+    let length = NLMSG_HEADER + CN_MSG_HEADER + event.len();
+    let mut data = (length as u32).to_ne_bytes().to_vec();
+    data.resize(NLMSG_HEADER + CN_MSG_HEADER, 0);
+    data.extend_from_slice(event);
+    data
+  }
+
+  #[test]
+  fn splits_datagrams() {
+    let mut data = message(&event(PROC_EVENT_EXEC, &[20, 20]));
+    data.extend(message(&event(PROC_EVENT_EXEC, &[30, 30])));
+    let pids: Vec<i32> = parse_messages(&data)
+      .iter()
+      .map(|event| match event {
+        Event::Exec { pid } => *pid,
+        _ => 0,
+      })
+      .collect();
+    assert_eq!(pids, [20, 30]);
+  }
+
+  #[test]
+  fn survives_malformed_messages() {
+    // Lengths between the netlink and the cn_msg header are skipped over
+    for length in [16u32, 20, 35] {
+      let mut data = length.to_ne_bytes().to_vec();
+      data.resize((length as usize + 3) & !3, 0);
+      data.extend(message(&event(PROC_EVENT_EXEC, &[20, 20])));
+      assert_eq!(parse_messages(&data).len(), 1, "length {}", length);
+    }
+
+    // Lengths that cannot be stepped over, or that run past the end, stop the parsing
+    let valid = message(&event(PROC_EVENT_EXEC, &[20, 20]));
+    for length in [0u32, 4, 15, 1000] {
+      let mut data = length.to_ne_bytes().to_vec();
+      data.resize(16, 0);
+      data.extend(&valid);
+      assert!(parse_messages(&data).is_empty(), "length {}", length);
+    }
+    assert!(parse_messages(&valid[..valid.len() - 1]).is_empty());
+    assert!(parse_messages(&[]).is_empty());
+  }
+
+  #[test]
+  fn describes_wait_statuses() {
+    assert_eq!(describe_status(Some(0)), "exit code 0");
+    assert_eq!(describe_status(Some(3 << 8)), "exit code 3");
+    assert_eq!(describe_status(Some(9)), "killed by signal 9");
+    assert_eq!(describe_status(None), "exit code unknown");
+  }
+}
@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fixture() -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc")
+}
+
+fn process(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_process"))
+    .arg("--proc-root")
+    .arg(fixture())
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn lists_the_fixture_processes() {
+  let output = process(&["-o", "pid,name,cmd"]);
+  assert!(output.status.success());
+  let text = stdout(&output);
+  assert!(text.contains("| 42  | Web Content "));
+  assert!(text.contains("nginx: master process nginx -g daemon off;"));
+  assert!(text.contains("[kthreadd]"));
+  // Nothing from the real /proc leaks in
+  assert_eq!(text.matches("| nginx ").count(), 3);
+  assert!(!text.contains("| process "));
+}
+
+#[test]
+fn proc_root_from_the_environment() {
+  let output = Command::new(env!("CARGO_BIN_EXE_process"))
+    .env("PROC_ROOT", fixture())
+    .args(["-t"])
+    .output()
+    .unwrap();
+  assert_eq!(
+    stdout(&output),
+    "systemd\n├─ Web Content\n└─ nginx\n   └─ 2*[nginx]\nkthreadd\n└─ kworker/0:1-events\n"
+  );
+}
+
+#[test]
+fn filters_by_container() {
+  let text = stdout(&process(&["-c", "3f2a", "-o", "pid,container"]));
+  assert!(text.contains("| 100 | 3f2a9c1b7d4e |"));
+  assert!(!text.contains("| 42 "));
+
+  let text = stdout(&process(&["-c", "host", "-n", "^k", "-o", "pid,name"]));
+  assert!(text.contains("kthreadd") && text.contains("kworker/0:1-events"));
+  assert!(!text.contains("nginx"));
+}
+
+#[test]
+fn finds_the_process_holding_a_port() {
+  let text = stdout(&process(&["net", "--port", "8080"]));
+  assert!(
+    text.contains("| tcp   | 0.0.0.0:8080 | 0.0.0.0:0   | LISTEN    | 100,101 | nginx,nginx |")
+  );
+  assert!(!text.contains(":22"));
+}
+
+#[test]
+fn lists_descriptors() {
+  let text = stdout(&process(&["files", "100"]));
+  assert!(text.contains("socket:[3003] (tcp 0.0.0.0:8080 -> 0.0.0.0:0 LISTEN)"));
+  assert!(text.contains("socket:[6006] (unix stream /run/my app.sock LISTEN)"));
+
+  let output = process(&["files", "12345"]);
+  assert!(!output.status.success());
+}
+
+#[test]
+fn kill_refuses_a_fake_root() {
+  let output = process(&["kill", "-n", "nginx", "--yes"]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn shows_stats() {
+  let text = stdout(&process(&["stats", "42"]));
+  assert!(text.contains("| vm_rss                |      204800 | KiB      |"));
+  assert!(!process(&["stats", "12345"]).status.success());
+}
+
+#[test]
+fn records_stats_to_csv() {
+  let file = std::env::temp_dir().join(format!("process-stats-{}.csv", std::process::id()));
+  let _ = std::fs::remove_file(&file);
+  let file_arg = file.to_str().unwrap();
+
+  // A second run appends without repeating the header
+  for _ in 0..2 {
+    let output = process(&["stats", "42", "-r", file_arg, "-i", "0", "-c", "2"]);
+    assert!(output.status.success());
+  }
+  let csv = std::fs::read_to_string(&file).unwrap();
+  std::fs::remove_file(&file).unwrap();
+
+  let lines: Vec<&str> = csv.lines().collect();
+  assert_eq!(lines.len(), 5);
+  assert!(lines[0].starts_with("timestamp,rchar,"));
+  assert!(
+    lines[1..]
+      .iter()
+      .all(|line| line.contains(",1048576,65536,2500,800,"))
+  );
+}
+
+#[test]
+fn stops_recording_when_the_pid_is_reused() {
+  let root = std::env::temp_dir().join(format!("process-reuse-{}", std::process::id()));
+  let process_dir = root.join("42");
+  std::fs::create_dir_all(&process_dir).unwrap();
+  for name in ["stat", "status", "io", "schedstat", "smaps_rollup"] {
+    std::fs::copy(fixture().join("42").join(name), process_dir.join(name)).unwrap();
+  }
+  let file = root.join("stats.csv");
+
+  let child = Command::new(env!("CARGO_BIN_EXE_process"))
+    .arg("--proc-root")
+    .arg(&root)
+    .args(["stats", "42", "-i", "2", "-c", "5", "-r"])
+    .arg(&file)
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+
+  // Between the first and second sample, another process starts with the same PID.
+  // Every row is flushed, so the first one shows up while the recorder sleeps.
+  let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+  while std::fs::read_to_string(&file).map_or(0, |csv| csv.lines().count()) < 2 {
+    assert!(
+      std::time::Instant::now() < deadline,
+      "no sample was recorded"
+    );
+    std::thread::sleep(std::time::Duration::from_millis(10));
+  }
+  let stat = std::fs::read_to_string(process_dir.join("stat")).unwrap();
+  let (name, rest) = stat.split_at(stat.rfind(')').unwrap() + 1);
+  let mut fields: Vec<String> = rest.split_whitespace().map(String::from).collect();
+  // The start time is field 22, the 20th after the name
+  fields[19] = (fields[19].parse::<u64>().unwrap() + 100).to_string();
+  std::fs::write(
+    process_dir.join("stat"),
+    format!("{} {}\n", name, fields.join(" ")),
+  )
+  .unwrap();
+
+  let output = child.wait_with_output().unwrap();
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let csv = std::fs::read_to_string(&file).unwrap();
+  std::fs::remove_dir_all(&root).unwrap();
+
+  assert!(output.status.success());
+  assert!(
+    stderr.contains("Process 42 is gone after 1 samples, its PID now belongs to another process")
+  );
+  assert_eq!(csv.lines().count(), 2);
+}
@@ -0,0 +1,103 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::memory::Method;
+use crate::search::PatternKind;
+
+/// Read the memory of another process through /proc
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, name = "read_memory")]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Commands,
+
+  /// Where procfs is mounted; point it at a copied or fake tree to read maps from there
+  #[arg(long, env = "PROC_ROOT", default_value = "/proc", global = true)]
+  pub proc_root: PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+  /// Print a hexdump of part of a process's memory
+  Dump {
+    /// Process to read
+    pid: i32,
+    /// Address in hex, or the name of a mapping like "[heap]" or "libc" to start at
+    location: String,
+    /// Number of bytes to read, at most up to the end of the mapping
+    #[arg(short = 'n', long, default_value_t = 256)]
+    length: usize,
+    /// How to read the memory
+    #[arg(short, long, value_enum, default_value = "mem")]
+    method: Method,
+  },
+  /// Show the mappings of a process with their memory use, like pmap -x
+  Maps {
+    /// Process to show
+    pid: i32,
+    /// Only show the totals per file or kind of memory
+    #[arg(short, long)]
+    grouped: bool,
+  },
+  /// Search the readable mappings of a process for a value
+  Search {
+    /// Process to search
+    pid: i32,
+    /// What to look for, read according to --type
+    pattern: String,
+    /// How to read the pattern
+    #[arg(short = 't', long = "type", value_enum, default_value = "hex")]
+    kind: PatternKind,
+    /// Size in bits of an integer pattern: 8, 16, 32 or 64
+    #[arg(short, long, default_value_t = 32)]
+    width: u32,
+    /// Only search mappings whose name contains this text
+    #[arg(short, long)]
+    region: Option<String>,
+    /// How to read the memory
+    #[arg(short, long, value_enum, default_value = "mem")]
+    method: Method,
+  },
+  /// Save regions of a process's memory to a file, to compare with `diff` later
+  Snapshot {
+    /// Process to read
+    pid: i32,
+    /// File to write
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Save the mappings whose name contains this text; may be repeated.
+    /// By default the heap and writable anonymous memory are saved.
+    #[arg(short, long)]
+    region: Vec<String>,
+    /// How to read the memory
+    #[arg(short, long, value_enum, default_value = "mem")]
+    method: Method,
+  },
+  /// Show which bytes changed between two snapshots
+  Diff {
+    /// The earlier snapshot
+    old: PathBuf,
+    /// The later snapshot
+    new: PathBuf,
+    /// Join changes separated by at most this many unchanged bytes
+    #[arg(short, long, default_value_t = 8)]
+    gap: usize,
+    /// Print the old and new bytes of every change
+    #[arg(short = 'x', long)]
+    hexdump: bool,
+  },
+  /// Print the printable strings in the memory of a process, like strings(1)
+  Strings {
+    /// Process to read
+    pid: i32,
+    /// Shortest run of printable characters to print
+    #[arg(short = 'n', long, default_value_t = 4)]
+    min_length: usize,
+    /// Only read mappings whose name contains this text
+    #[arg(short, long)]
+    region: Option<String>,
+    /// How to read the memory
+    #[arg(short, long, value_enum, default_value = "mem")]
+    method: Method,
+  },
+}
@@ -0,0 +1,48 @@
+/// Format bytes like `hexdump -C`: the address, 16 bytes in two groups of
+/// eight, then the printable ASCII characters with '.' for the rest
+pub fn hexdump(address: u64, bytes: &[u8]) -> String {
+  let mut lines = Vec::new();
+  for (index, chunk) in bytes.chunks(16).enumerate() {
+    let mut hex = String::with_capacity(49);
+    for (i, byte) in chunk.iter().enumerate() {
+      if i == 8 {
+        hex.push(' ');
+      }
+      hex.push_str(&format!("{:02x} ", byte));
+    }
+
+    let ascii: String = chunk
+      .iter()
+      .map(|&byte| {
+        if byte.is_ascii_graphic() || byte == b' ' {
+          byte as char
+        } else {
+          '.'
+        }
+      })
+      .collect();
+    lines.push(format!(
+      "{:016x}  {:<49} |{}|",
+      address + index as u64 * 16,
+      hex,
+      ascii
+    ));
+  }
+  lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn full_and_partial_lines() {
+    let dump = hexdump(0x7ffd0000, b"Hello, world!\n\x00\x01ABC");
+    assert_eq!(
+      dump,
+      "000000007ffd0000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|\n\
+       000000007ffd0010  41 42 43                                          |ABC|"
+    );
+    assert_eq!(hexdump(0, &[]), "");
+  }
+}
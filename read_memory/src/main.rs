@@ -1,3 +1,373 @@
+use clap::Parser;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+mod cli;
+mod hexdump;
+mod maps;
+mod memory;
+mod search;
+mod snapshot;
+
+use cli::{Cli, Commands};
+use hexdump::hexdump;
+use maps::{Region, Usage, group_usage, read_maps, read_smaps};
+use memory::{Method, ProcessMemory};
+use search::{Found, Pattern, PatternKind};
+use snapshot::Snapshot;
+
 fn main() {
-    println!("Hello, world!");
+  let cli = Cli::parse();
+  let proc_root = cli.proc_root.as_path();
+
+  let result = match cli.command {
+    Commands::Dump {
+      pid,
+      location,
+      length,
+      method,
+    } => dump(proc_root, pid, &location, length, method),
+    Commands::Maps { pid, grouped } => show_maps(proc_root, pid, grouped),
+    Commands::Search {
+      pid,
+      pattern,
+      kind,
+      width,
+      region,
+      method,
+    } => search_memory(
+      proc_root,
+      pid,
+      &pattern,
+      kind,
+      width,
+      region.as_deref(),
+      method,
+    ),
+    Commands::Snapshot {
+      pid,
+      output,
+      region,
+      method,
+    } => save_snapshot(proc_root, pid, &output, &region, method),
+    Commands::Diff {
+      old,
+      new,
+      gap,
+      hexdump,
+    } => diff_snapshots(&old, &new, gap, hexdump),
+    Commands::Strings {
+      pid,
+      min_length,
+      region,
+      method,
+    } => list_strings(proc_root, pid, min_length, region.as_deref(), method),
+  };
+  if let Err(e) = result {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
+  }
+}
+
+// The most a single dump reads, however large the mapping is
+const MAX_DUMP: usize = 16 << 20;
+
+fn dump(
+  proc_root: &Path,
+  pid: i32,
+  location: &str,
+  length: usize,
+  method: Method,
+) -> io::Result<()> {
+  let regions = read_maps(proc_root, pid)?;
+  let (address, region) = resolve(&regions, location).ok_or_else(|| {
+    io::Error::new(
+      io::ErrorKind::NotFound,
+      format!("'{}' is not mapped in process {}", location, pid),
+    )
+  })?;
+  if !region.permissions.read {
+    eprintln!(
+      "Warning: {} is not readable, the read will likely fail",
+      region.name()
+    );
+  }
+
+  // The buffer is as long as the length asked for, so it stops at the end of the mapping
+  let available = ((region.end - address) as usize).min(MAX_DUMP);
+  if length > available {
+    eprintln!(
+      "Reading {} of the {} bytes asked for, up to {}",
+      available,
+      length,
+      if available == MAX_DUMP {
+        "the limit of a single dump"
+      } else {
+        "the end of the mapping"
+      }
+    );
+  }
+  let length = length.min(available);
+
+  let memory = ProcessMemory::open(proc_root, pid, method)?;
+  let bytes = memory.read(address, length)?;
+  println!(
+    "{:x}-{:x} {} {} ({} bytes)",
+    region.start,
+    region.end,
+    region.permissions,
+    region.name(),
+    region.len()
+  );
+  println!("{}", hexdump(address, &bytes));
+  if bytes.len() < length {
+    eprintln!(
+      "Only {} of {} bytes could be read, the rest is not mapped",
+      bytes.len(),
+      length
+    );
+  }
+  Ok(())
+}
+
+fn show_maps(proc_root: &Path, pid: i32, grouped: bool) -> io::Result<()> {
+  let mappings = read_smaps(proc_root, pid)?;
+  let mut total = Usage::default();
+  for (_, usage) in &mappings {
+    total.add(usage);
+  }
+
+  if !grouped {
+    println!(
+      "{:<16}  {:<4}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  Mapping",
+      "Address", "Perm", "Size KiB", "RSS", "PSS", "Swap", "Dirty"
+    );
+    for (region, usage) in &mappings {
+      println!(
+        "{:016x}  {}  {}  {}",
+        region.start,
+        region.permissions,
+        usage_columns(usage),
+        region.name()
+      );
+    }
+    println!("{:<22}  {}", "Total", usage_columns(&total));
+    println!();
+  }
+
+  let groups = group_usage(&mappings);
+  println!(
+    "{:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>4}  Mapping",
+    "Size KiB", "RSS", "PSS", "Swap", "Dirty", "Maps"
+  );
+  for (name, usage, count) in &groups {
+    println!("{}  {:>4}  {}", usage_columns(usage), count, name);
+  }
+  println!("{}  {:>4}  Total", usage_columns(&total), mappings.len());
+  Ok(())
+}
+
+fn usage_columns(usage: &Usage) -> String {
+  format!(
+    "{:>10}  {:>10}  {:>10}  {:>10}  {:>10}",
+    usage.size, usage.rss, usage.pss, usage.swap, usage.dirty
+  )
+}
+
+fn search_memory(
+  proc_root: &Path,
+  pid: i32,
+  text: &str,
+  kind: PatternKind,
+  width: u32,
+  region: Option<&str>,
+  method: Method,
+) -> io::Result<()> {
+  let pattern = Pattern::parse(kind, text, width)
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+  let regions = selected_regions(proc_root, pid, region)?;
+  let memory = ProcessMemory::open(proc_root, pid, method)?;
+
+  let found = search::search(&memory, &regions, &pattern);
+  for Found {
+    address, region, ..
+  } in &found
+  {
+    println!(
+      "{:016x}  {}+0x{:x}",
+      address,
+      region.name(),
+      address - region.start
+    );
+  }
+  println!("{} matches", found.len());
+  Ok(())
+}
+
+fn list_strings(
+  proc_root: &Path,
+  pid: i32,
+  min_length: usize,
+  region: Option<&str>,
+  method: Method,
+) -> io::Result<()> {
+  let regions = selected_regions(proc_root, pid, region)?;
+  let memory = ProcessMemory::open(proc_root, pid, method)?;
+
+  for Found {
+    address,
+    region,
+    text,
+  } in search::strings(&memory, &regions, min_length.max(1))
+  {
+    println!(
+      "{:016x}  {}  {}",
+      address,
+      region.name(),
+      text.unwrap_or_default()
+    );
+  }
+  Ok(())
+}
+
+// Selections larger than this get a warning, as a process with big sparse
+// reservations, like a JVM heap, has that much writable anonymous memory
+const LARGE_SNAPSHOT: u64 = 1 << 30;
+
+fn save_snapshot(
+  proc_root: &Path,
+  pid: i32,
+  output: &Path,
+  names: &[String],
+  method: Method,
+) -> io::Result<()> {
+  let regions = read_maps(proc_root, pid)?;
+  let selected: Vec<&Region> = regions
+    .iter()
+    .filter(|region| {
+      if names.is_empty() {
+        snapshot::default_selection(region)
+      } else {
+        names
+          .iter()
+          .any(|name| region.name().contains(name.as_str()))
+      }
+    })
+    .collect();
+  if selected.is_empty() {
+    return Err(io::Error::new(
+      io::ErrorKind::NotFound,
+      format!("No mapping of process {} was selected", pid),
+    ));
+  }
+
+  let size: u64 = selected.iter().map(|region| region.len()).sum();
+  if size > LARGE_SNAPSHOT {
+    eprintln!(
+      "Warning: the selected mappings span {} MiB and the snapshot file can grow as large; \
+       name the mappings to save with --region",
+      size >> 20
+    );
+  }
+
+  let memory = ProcessMemory::open(proc_root, pid, method)?;
+  let mut file = BufWriter::new(File::create(output)?);
+  let (regions, bytes) = Snapshot::save(pid, &memory, &selected, &mut file)?;
+  file.flush()?;
+
+  println!(
+    "Saved {} regions ({} bytes) of process {} to {}",
+    regions,
+    bytes,
+    pid,
+    output.display()
+  );
+  Ok(())
+}
+
+fn diff_snapshots(old: &Path, new: &Path, gap: usize, show_bytes: bool) -> io::Result<()> {
+  let load = |path: &Path| -> io::Result<Snapshot> {
+    Snapshot::read(&mut BufReader::new(File::open(path)?))
+      .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+  };
+  let (old, new) = (load(old)?, load(new)?);
+  if old.pid != new.pid {
+    eprintln!(
+      "Warning: the snapshots are of different processes ({} and {})",
+      old.pid, new.pid
+    );
+  }
+  println!(
+    "Process {}, snapshots taken {} s apart",
+    new.pid,
+    new.time.saturating_sub(old.time)
+  );
+
+  let diff = old.diff(&new, gap);
+  for region in &diff.removed {
+    println!(
+      "Only in old: {:x}-{:x} {}",
+      region.start, region.end, region.name
+    );
+  }
+  for region in &diff.added {
+    println!(
+      "Only in new: {:x}-{:x} {}",
+      region.start, region.end, region.name
+    );
+  }
+  for (before, after) in &diff.resized {
+    println!(
+      "Resized: {} {:x}-{:x} to {:x}-{:x}",
+      after.name, before.start, before.end, after.start, after.end
+    );
+  }
+
+  for change in &diff.changes {
+    println!(
+      "{:016x}  {}+0x{:x}  {} bytes",
+      change.address,
+      change.region.name,
+      change.address - change.region.start,
+      change.new.len()
+    );
+    if show_bytes {
+      println!("old:\n{}", hexdump(change.address, change.old));
+      println!("new:\n{}", hexdump(change.address, change.new));
+    }
+  }
+  let bytes: usize = diff.changes.iter().map(|change| change.new.len()).sum();
+  println!("{} changed ranges, {} bytes", diff.changes.len(), bytes);
+  Ok(())
+}
+
+// The mappings of a process, or only those whose name contains `name`
+fn selected_regions(proc_root: &Path, pid: i32, name: Option<&str>) -> io::Result<Vec<Region>> {
+  let mut regions = read_maps(proc_root, pid)?;
+  if let Some(name) = name {
+    regions.retain(|region| region.name().contains(name));
+    if regions.is_empty() {
+      return Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No mapping of process {} matches '{}'", pid, name),
+      ));
+    }
+  }
+  Ok(regions)
+}
+
+// An address in hex, with or without 0x, or else the start of the first
+// mapping whose name contains the text
+fn resolve<'a>(regions: &'a [Region], location: &str) -> Option<(u64, &'a Region)> {
+  let hex = location.strip_prefix("0x").unwrap_or(location);
+  if let Ok(address) = u64::from_str_radix(hex, 16) {
+    let region = regions.iter().find(|region| region.contains(address))?;
+    return Some((address, region));
+  }
+
+  let region = regions
+    .iter()
+    .find(|region| region.name().contains(location))?;
+  Some((region.start, region))
 }
@@ -0,0 +1,351 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Access rights of a mapping, the "rwxp" column of /proc/<pid>/maps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+  pub read: bool,
+  pub write: bool,
+  pub execute: bool,
+  /// Shared with other processes (s) rather than copy-on-write private (p)
+  pub shared: bool,
+}
+
+/// What backs a mapping, from its pathname column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionKind {
+  /// A mapped file; " (deleted)" is kept when the file was removed
+  File(String),
+  Heap,
+  Stack,
+  /// The kernel's virtual shared objects ([vdso], [vvar], [vsyscall])
+  Kernel(String),
+  /// Anonymous memory: malloc arenas, thread stacks, JIT code, ...
+  Anonymous,
+  /// Other pseudo paths such as [anon:name] or [stack:1234]
+  Other(String),
+}
+
+/// How mappings without a pathname are listed and grouped
+pub const ANONYMOUS: &str = "[anon]";
+
+/// One line of /proc/<pid>/maps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+  pub start: u64,
+  /// One past the last address
+  pub end: u64,
+  pub permissions: Permissions,
+  /// Offset into the file, for file mappings
+  pub offset: u64,
+  /// Device as "major:minor" in hex
+  pub device: String,
+  pub inode: u64,
+  pub kind: RegionKind,
+}
+
+impl Region {
+  /// Parse a line like
+  /// "7ffd0f37e000-7ffd0f39f000 rw-p 00000000 00:00 0    [stack]"
+  pub fn parse(line: &str) -> Option<Region> {
+    // Five fixed fields, then the pathname which may contain spaces
+    let mut fields = Vec::with_capacity(5);
+    let mut rest = line.trim_start();
+    while fields.len() < 5 && !rest.is_empty() {
+      let (field, tail) = rest.split_once(' ').unwrap_or((rest, ""));
+      fields.push(field);
+      rest = tail.trim_start();
+    }
+    let [range, permissions, offset, device, inode] = fields[..] else {
+      return None;
+    };
+    let (start, end) = range.split_once('-')?;
+    let permissions = permissions.as_bytes();
+    if permissions.len() != 4 {
+      return None;
+    }
+    let device = device.to_string();
+    let inode = inode.parse().ok()?;
+    let pathname = Some(rest.trim_end()).filter(|name| !name.is_empty());
+
+    let kind = match pathname {
+      None => RegionKind::Anonymous,
+      Some("[heap]") => RegionKind::Heap,
+      Some("[stack]") => RegionKind::Stack,
+      Some(name @ ("[vdso]" | "[vvar]" | "[vvar_vclock]" | "[vsyscall]")) => {
+        RegionKind::Kernel(name.to_string())
+      }
+      Some(name) if name.starts_with('[') => RegionKind::Other(name.to_string()),
+      Some(name) => RegionKind::File(name.to_string()),
+    };
+
+    Some(Region {
+      start: u64::from_str_radix(start, 16).ok()?,
+      end: u64::from_str_radix(end, 16).ok()?,
+      permissions: Permissions {
+        read: permissions[0] == b'r',
+        write: permissions[1] == b'w',
+        execute: permissions[2] == b'x',
+        shared: permissions[3] == b's',
+      },
+      offset: u64::from_str_radix(offset, 16).ok()?,
+      device,
+      inode,
+      kind,
+    })
+  }
+
+  pub fn len(&self) -> u64 {
+    self.end - self.start
+  }
+
+  pub fn contains(&self, address: u64) -> bool {
+    (self.start..self.end).contains(&address)
+  }
+
+  /// The pathname, or "[anon]" for anonymous memory
+  pub fn name(&self) -> &str {
+    match &self.kind {
+      RegionKind::File(name) | RegionKind::Kernel(name) | RegionKind::Other(name) => name,
+      RegionKind::Heap => "[heap]",
+      RegionKind::Stack => "[stack]",
+      RegionKind::Anonymous => ANONYMOUS,
+    }
+  }
+}
+
+impl fmt::Display for Permissions {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let flag = |set: bool, c: char| if set { c } else { '-' };
+    write!(
+      f,
+      "{}{}{}{}",
+      flag(self.read, 'r'),
+      flag(self.write, 'w'),
+      flag(self.execute, 'x'),
+      if self.shared { 's' } else { 'p' }
+    )
+  }
+}
+
+/// All mappings of a process, in address order
+pub fn read_maps(proc_root: &Path, pid: i32) -> io::Result<Vec<Region>> {
+  let content = fs::read_to_string(proc_root.join(pid.to_string()).join("maps"))?;
+  Ok(content.lines().filter_map(Region::parse).collect())
+}
+
+/// Memory use of one mapping from /proc/<pid>/smaps, in KiB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+  pub size: u64,
+  /// Resident in RAM
+  pub rss: u64,
+  /// Proportional set size: private pages plus a fair share of shared ones
+  pub pss: u64,
+  pub swap: u64,
+  /// Modified pages, shared and private, that must be written back or swapped
+  pub dirty: u64,
+}
+
+impl Usage {
+  pub fn add(&mut self, other: &Usage) {
+    self.size += other.size;
+    self.rss += other.rss;
+    self.pss += other.pss;
+    self.swap += other.swap;
+    self.dirty += other.dirty;
+  }
+}
+
+/// Parse the contents of /proc/<pid>/smaps: every maps line followed by
+/// "Key:   N kB" lines
+pub fn parse_smaps(content: &str) -> Vec<(Region, Usage)> {
+  let mut mappings: Vec<(Region, Usage)> = Vec::new();
+  for line in content.lines() {
+    // Mapping lines have a colon too, in the device, but after spaces
+    if let Some((key, value)) = line.split_once(':')
+      && !key.contains(' ')
+    {
+      let Some((_, usage)) = mappings.last_mut() else {
+        continue;
+      };
+      let value = value
+        .split_whitespace()
+        .next()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+      match key {
+        "Size" => usage.size = value,
+        "Rss" => usage.rss = value,
+        "Pss" => usage.pss = value,
+        "Swap" => usage.swap = value,
+        "Shared_Dirty" | "Private_Dirty" => usage.dirty += value,
+        _ => {}
+      }
+    } else if let Some(region) = Region::parse(line) {
+      mappings.push((region, Usage::default()));
+    }
+  }
+  mappings
+}
+
+/// All mappings of a process with their memory use, in address order
+pub fn read_smaps(proc_root: &Path, pid: i32) -> io::Result<Vec<(Region, Usage)>> {
+  let content = fs::read_to_string(proc_root.join(pid.to_string()).join("smaps"))?;
+  Ok(parse_smaps(&content))
+}
+
+/// The memory use of every file, and of each kind of pseudo mapping, with the
+/// number of mappings it spans; all anonymous memory is one "[anon]" group.
+/// The largest PSS comes first.
+pub fn group_usage(mappings: &[(Region, Usage)]) -> Vec<(&str, Usage, usize)> {
+  let mut groups: Vec<(&str, Usage, usize)> = Vec::new();
+  for (region, usage) in mappings {
+    let name = region.name();
+    match groups.iter_mut().find(|(group, _, _)| *group == name) {
+      Some((_, group, count)) => {
+        group.add(usage);
+        *count += 1;
+      }
+      None => groups.push((name, *usage, 1)),
+    }
+  }
+  groups.sort_by(|a, b| b.1.pss.cmp(&a.1.pss).then(a.0.cmp(b.0)));
+  groups
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_file_and_pseudo_mappings() {
+    let region = Region::parse(
+      "55d425578000-55d42557d000 r-xp 00002000 fe:00 317563                     /usr/bin/cat",
+    )
+    .unwrap();
+    assert_eq!(region.start, 0x55d425578000);
+    assert_eq!(region.len(), 0x5000);
+    assert_eq!(region.permissions.to_string(), "r-xp");
+    assert!(region.permissions.execute && !region.permissions.write);
+    assert_eq!(region.offset, 0x2000);
+    assert_eq!(region.device, "fe:00");
+    assert_eq!(region.inode, 317563);
+    assert_eq!(region.kind, RegionKind::File("/usr/bin/cat".to_string()));
+
+    let stack =
+      Region::parse("7ffd0f37e000-7ffd0f39f000 rw-p 00000000 00:00 0          [stack]").unwrap();
+    assert_eq!(stack.kind, RegionKind::Stack);
+    assert!(stack.contains(0x7ffd0f37e000) && !stack.contains(0x7ffd0f39f000));
+
+    let vsyscall =
+      Region::parse("ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0  [vsyscall]").unwrap();
+    assert_eq!(vsyscall.kind, RegionKind::Kernel("[vsyscall]".to_string()));
+    assert!(!vsyscall.permissions.read);
+  }
+
+  #[test]
+  fn anonymous_and_odd_names() {
+    let anon = Region::parse("7f2c15e00000-7f2c15e21000 rw-p 00000000 00:00 0 ").unwrap();
+    assert_eq!(anon.kind, RegionKind::Anonymous);
+    assert_eq!(anon.name(), "[anon]");
+
+    let deleted = Region::parse(
+      "7f0000000000-7f0000001000 rw-s 00000000 00:05 1234  /dev/shm/my file (deleted)",
+    )
+    .unwrap();
+    assert_eq!(deleted.name(), "/dev/shm/my file (deleted)");
+    assert!(deleted.permissions.shared);
+
+    let named = Region::parse("7f0000000000-7f0000001000 rw-p 00000000 00:00 0  [anon:jemalloc]");
+    assert_eq!(
+      named.unwrap().kind,
+      RegionKind::Other("[anon:jemalloc]".to_string())
+    );
+
+    assert!(Region::parse("").is_none());
+    assert!(Region::parse("zz-10 rw-p 0 00:00 0").is_none());
+  }
+
+  #[test]
+  fn reads_own_maps() {
+    let regions = read_maps(Path::new("/proc"), std::process::id() as i32).unwrap();
+    assert!(
+      regions
+        .iter()
+        .any(|region| region.kind == RegionKind::Stack)
+    );
+    assert!(regions.windows(2).all(|pair| pair[0].end <= pair[1].start));
+  }
+
+  #[test]
+  fn usage_from_smaps() {
+    let smaps = "\
+55daa0729000-55daa072b000 r--p 00000000 fe:00 317783                     /usr/bin/head
+Size:                  8 kB
+Rss:                   8 kB
+Pss:                   4 kB
+Pss_Dirty:             4 kB
+Shared_Dirty:          4 kB
+Private_Dirty:         4 kB
+Swap:                  0 kB
+VmFlags: rd mr mw me
+7ffd0f37e000-7ffd0f39f000 rw-p 00000000 00:00 0                          [stack]
+Size:                132 kB
+Rss:                  20 kB
+Pss:                  20 kB
+Swap:                 12 kB
+VmFlags: rd wr mr mw me gd ac
+";
+    let mappings = parse_smaps(smaps);
+    assert_eq!(mappings.len(), 2);
+    assert_eq!(mappings[0].0.name(), "/usr/bin/head");
+    assert_eq!(
+      mappings[0].1,
+      Usage {
+        size: 8,
+        rss: 8,
+        pss: 4,
+        swap: 0,
+        dirty: 8
+      }
+    );
+    assert_eq!(mappings[1].0.kind, RegionKind::Stack);
+    assert_eq!(mappings[1].1.swap, 12);
+
+    let mut total = mappings[0].1;
+    total.add(&mappings[1].1);
+    assert_eq!((total.size, total.rss, total.dirty), (140, 28, 8));
+  }
+
+  #[test]
+  fn groups_usage_by_file_and_anonymous_memory() {
+    let smaps = "\
+55daa0729000-55daa072b000 r--p 00000000 fe:00 317783 /usr/bin/head
+Pss:                   4 kB
+55daa072b000-55daa072f000 r-xp 00002000 fe:00 317783 /usr/bin/head
+Pss:                   8 kB
+7f2c15e00000-7f2c15e21000 rw-p 00000000 00:00 0 
+Pss:                  64 kB
+7f2c15e21000-7f2c15f00000 ---p 00000000 00:00 0 
+Pss:                   0 kB
+7f2c16000000-7f2c16001000 rw-p 00000000 00:00 0  [anon:jemalloc]
+Pss:                   4 kB
+";
+    let mappings = parse_smaps(smaps);
+    let groups: Vec<(&str, u64, usize)> = group_usage(&mappings)
+      .into_iter()
+      .map(|(name, usage, count)| (name, usage.pss, count))
+      .collect();
+    assert_eq!(
+      groups,
+      [
+        ("[anon]", 64, 2),
+        ("/usr/bin/head", 12, 2),
+        ("[anon:jemalloc]", 4, 1)
+      ]
+    );
+  }
+}
@@ -0,0 +1,265 @@
+use clap::ValueEnum;
+
+use crate::maps::Region;
+use crate::memory::{CHUNK, ProcessMemory};
+
+/// How the text of a search pattern is turned into bytes
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+  /// Hex bytes like "de ad ?? ef", where ?? matches any byte
+  Hex,
+  /// The UTF-8 bytes of a string
+  Utf8,
+  /// The UTF-16 (little endian) code units of a string, as Windows and Java use
+  Utf16,
+  /// An integer of --width bits in the byte order of this machine
+  Int,
+}
+
+/// A byte sequence to look for; None matches any byte
+#[derive(Debug, PartialEq, Eq)]
+pub struct Pattern(Vec<Option<u8>>);
+
+impl Pattern {
+  pub fn parse(kind: PatternKind, text: &str, width: u32) -> Result<Pattern, String> {
+    let pattern = match kind {
+      PatternKind::Hex => Pattern::hex(text)?,
+      PatternKind::Utf8 => Pattern(text.bytes().map(Some).collect()),
+      PatternKind::Utf16 => Pattern(
+        text
+          .encode_utf16()
+          .flat_map(u16::to_le_bytes)
+          .map(Some)
+          .collect(),
+      ),
+      PatternKind::Int => Pattern::integer(text, width)?,
+    };
+    if pattern.0.is_empty() {
+      return Err("The pattern is empty".to_string());
+    }
+    Ok(pattern)
+  }
+
+  // Pairs of hex digits, optionally separated by spaces; "??" or "?" is a wildcard
+  fn hex(text: &str) -> Result<Pattern, String> {
+    let mut bytes = Vec::new();
+    for word in text.split_whitespace() {
+      if word == "?" || word == "??" {
+        bytes.push(None);
+        continue;
+      }
+      if word.len() % 2 != 0 {
+        return Err(format!("'{}' is not a whole number of bytes", word));
+      }
+      for pair in word.as_bytes().chunks(2) {
+        let pair = std::str::from_utf8(pair).map_err(|_| format!("Invalid hex '{}'", word))?;
+        if pair == "??" {
+          bytes.push(None);
+        } else {
+          let byte = u8::from_str_radix(pair, 16).map_err(|_| format!("Invalid hex '{}'", word))?;
+          bytes.push(Some(byte));
+        }
+      }
+    }
+    Ok(Pattern(bytes))
+  }
+
+  // Decimal or 0x hex, negative numbers in two's complement
+  fn integer(text: &str, width: u32) -> Result<Pattern, String> {
+    if ![8, 16, 32, 64].contains(&width) {
+      return Err(format!(
+        "The width must be 8, 16, 32 or 64 bits, not {}",
+        width
+      ));
+    }
+    let (negative, digits) = match text.strip_prefix('-') {
+      Some(digits) => (true, digits),
+      None => (false, text),
+    };
+    let magnitude = match digits.strip_prefix("0x") {
+      Some(hex) => u128::from_str_radix(hex, 16),
+      None => digits.parse(),
+    }
+    .map_err(|_| format!("Invalid integer '{}'", text))?;
+
+    let bits = width as u128;
+    let fits = if negative {
+      magnitude <= 1 << (bits - 1)
+    } else {
+      magnitude < 1 << bits
+    };
+    if !fits {
+      return Err(format!("{} does not fit in {} bits", text, width));
+    }
+    let value = if negative {
+      magnitude.wrapping_neg()
+    } else {
+      magnitude
+    };
+
+    let bytes = value.to_ne_bytes();
+    let length = width as usize / 8;
+    // The low bytes are first on little endian machines and last on big endian ones
+    let bytes = if cfg!(target_endian = "little") {
+      &bytes[..length]
+    } else {
+      &bytes[bytes.len() - length..]
+    };
+    Ok(Pattern(bytes.iter().copied().map(Some).collect()))
+  }
+
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  /// Offsets of every (possibly overlapping) match in `haystack`
+  pub fn find_all(&self, haystack: &[u8]) -> Vec<usize> {
+    haystack
+      .windows(self.0.len())
+      .enumerate()
+      .filter(|(_, window)| {
+        self
+          .0
+          .iter()
+          .zip(window.iter())
+          .all(|(expected, byte)| expected.is_none_or(|expected| expected == *byte))
+      })
+      .map(|(offset, _)| offset)
+      .collect()
+  }
+}
+
+/// A match or string found in a process, with the mapping it lies in
+pub struct Found<'a> {
+  pub address: u64,
+  pub region: &'a Region,
+  /// The string, for `strings`
+  pub text: Option<String>,
+}
+
+/// Every match of `pattern` in the readable regions. Regions that cannot be
+/// read, like [vvar] or memory unmapped in the meantime, are skipped.
+pub fn search<'a>(
+  memory: &ProcessMemory,
+  regions: &'a [Region],
+  pattern: &Pattern,
+) -> Vec<Found<'a>> {
+  let mut found = Vec::new();
+  for region in regions.iter().filter(|region| region.permissions.read) {
+    let mut address = region.start;
+    while address < region.end {
+      let length = CHUNK.min((region.end - address) as usize);
+      let Ok(bytes) = memory.read(address, length) else {
+        break;
+      };
+      found.extend(pattern.find_all(&bytes).into_iter().map(|offset| Found {
+        address: address + offset as u64,
+        region,
+        text: None,
+      }));
+      if bytes.len() < length || address + length as u64 == region.end {
+        break;
+      }
+      // A match can only start in the last len - 1 bytes if it continues in the next chunk
+      address += (length - (pattern.len() - 1).min(length - 1)) as u64;
+    }
+  }
+  found
+}
+
+/// Runs of at least `min_length` printable ASCII characters, like strings(1)
+pub fn strings<'a>(
+  memory: &ProcessMemory,
+  regions: &'a [Region],
+  min_length: usize,
+) -> Vec<Found<'a>> {
+  let printable = |byte: u8| byte.is_ascii_graphic() || byte == b' ' || byte == b'\t';
+  let mut found = Vec::new();
+  for region in regions.iter().filter(|region| region.permissions.read) {
+    // A run can continue from one chunk into the next
+    let mut run: Vec<u8> = Vec::new();
+    let mut run_start = region.start;
+    let mut address = region.start;
+    while address < region.end {
+      let length = CHUNK.min((region.end - address) as usize);
+      let Ok(bytes) = memory.read(address, length) else {
+        break;
+      };
+      for (offset, &byte) in bytes.iter().enumerate() {
+        if printable(byte) {
+          if run.is_empty() {
+            run_start = address + offset as u64;
+          }
+          run.push(byte);
+        } else {
+          push_string(&mut found, region, run_start, &mut run, min_length);
+        }
+      }
+      if bytes.len() < length {
+        break;
+      }
+      address += bytes.len() as u64;
+    }
+    push_string(&mut found, region, run_start, &mut run, min_length);
+  }
+  found
+}
+
+fn push_string<'a>(
+  found: &mut Vec<Found<'a>>,
+  region: &'a Region,
+  address: u64,
+  run: &mut Vec<u8>,
+  min_length: usize,
+) {
+  if run.len() >= min_length {
+    found.push(Found {
+      address,
+      region,
+      text: Some(String::from_utf8_lossy(run).into_owned()),
+    });
+  }
+  run.clear();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn bytes(pattern: &Pattern) -> Vec<Option<u8>> {
+    pattern.0.clone()
+  }
+
+  #[test]
+  fn parses_every_kind() {
+    let hex = Pattern::parse(PatternKind::Hex, "de ad ?? ef", 32).unwrap();
+    assert_eq!(bytes(&hex), [Some(0xde), Some(0xad), None, Some(0xef)]);
+    let packed = Pattern::parse(PatternKind::Hex, "dead??ef", 32).unwrap();
+    assert_eq!(packed, hex);
+    assert!(Pattern::parse(PatternKind::Hex, "dea", 32).is_err());
+    assert!(Pattern::parse(PatternKind::Hex, "zz", 32).is_err());
+    assert!(Pattern::parse(PatternKind::Hex, "", 32).is_err());
+
+    let utf16 = Pattern::parse(PatternKind::Utf16, "hé", 32).unwrap();
+    assert_eq!(bytes(&utf16), [Some(b'h'), Some(0), Some(0xe9), Some(0)]);
+
+    let int = Pattern::parse(PatternKind::Int, "0x1234", 16).unwrap();
+    assert_eq!(bytes(&int), 0x1234u16.to_ne_bytes().map(Some));
+    let negative = Pattern::parse(PatternKind::Int, "-2", 32).unwrap();
+    assert_eq!(bytes(&negative), (-2i32).to_ne_bytes().map(Some));
+    assert!(Pattern::parse(PatternKind::Int, "256", 8).is_err());
+    assert!(Pattern::parse(PatternKind::Int, "-128", 8).is_ok());
+    assert!(Pattern::parse(PatternKind::Int, "-129", 8).is_err());
+    assert!(Pattern::parse(PatternKind::Int, "1", 12).is_err());
+  }
+
+  #[test]
+  fn finds_overlapping_matches_with_wildcards() {
+    let pattern = Pattern::parse(PatternKind::Hex, "aa ?? aa", 32).unwrap();
+    assert_eq!(
+      pattern.find_all(&[0xaa, 0x01, 0xaa, 0x02, 0xaa, 0xaa]),
+      [0, 2]
+    );
+    assert!(pattern.find_all(&[0xaa]).is_empty());
+  }
+}
@@ -0,0 +1,393 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::maps::{Region, RegionKind};
+use crate::memory::{CHUNK, ProcessMemory};
+
+// First line of a snapshot file
+const MAGIC: &str = "read_memory snapshot 1";
+
+/// The contents of some regions of a process at one moment.
+///
+/// On disk this is a text header per region followed by its raw bytes:
+/// ```text
+/// read_memory snapshot 1
+/// pid 1234 time 1700000000
+/// region 55d4265f1000 55d426612000 rw-p 135168 [heap]
+/// <135168 bytes>
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct Snapshot {
+  pub pid: i32,
+  /// Seconds since the Unix epoch
+  pub time: u64,
+  pub regions: Vec<SavedRegion>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SavedRegion {
+  pub start: u64,
+  pub end: u64,
+  pub permissions: String,
+  pub name: String,
+  /// Shorter than end - start when part of the region could not be read
+  pub data: Vec<u8>,
+}
+
+/// A range of bytes that differs between two snapshots of a region
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change<'a> {
+  pub address: u64,
+  pub old: &'a [u8],
+  pub new: &'a [u8],
+  pub region: &'a SavedRegion,
+}
+
+/// How two snapshots differ: changed ranges, then regions only in one of them
+pub struct Diff<'a> {
+  pub changes: Vec<Change<'a>>,
+  pub removed: Vec<&'a SavedRegion>,
+  pub added: Vec<&'a SavedRegion>,
+  /// Regions whose size changed, like a growing heap; only the common part is compared
+  pub resized: Vec<(&'a SavedRegion, &'a SavedRegion)>,
+}
+
+/// The regions snapshotted when none are named: the heap and anonymous
+/// memory, where a program keeps the state that changes
+pub fn default_selection(region: &Region) -> bool {
+  region.permissions.write && matches!(region.kind, RegionKind::Heap | RegionKind::Anonymous)
+}
+
+impl Snapshot {
+  /// Read the given regions into `out` chunk by chunk, so a large mapping
+  /// never has to fit in memory. Regions that cannot be read at all are left
+  /// out. Returns how many regions and bytes were saved.
+  pub fn save(
+    pid: i32,
+    memory: &ProcessMemory,
+    regions: &[&Region],
+    out: &mut (impl Write + Seek),
+  ) -> io::Result<(usize, u64)> {
+    let time = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |elapsed| elapsed.as_secs());
+    writeln!(out, "{}", MAGIC)?;
+    writeln!(out, "pid {} time {}", pid, time)?;
+
+    let (mut saved, mut total) = (0, 0);
+    for region in regions {
+      let length = region.end - region.start;
+      let read = |address: u64| memory.read(address, CHUNK.min((region.end - address) as usize));
+      let Ok(mut bytes) = read(region.start) else {
+        continue;
+      };
+
+      // The header announces the whole region. When the data ends early the
+      // length is rewritten in place, padded with zeros to the same width.
+      write!(
+        out,
+        "region {:x} {:x} {} ",
+        region.start, region.end, region.permissions
+      )?;
+      let length_at = out.stream_position()?;
+      writeln!(out, "{} {}", length, region.name())?;
+
+      let mut written = 0;
+      loop {
+        // A short read means the rest of the region could not be read
+        let short = bytes.len() < CHUNK.min((length - written) as usize);
+        out.write_all(&bytes)?;
+        written += bytes.len() as u64;
+        if short || written == length {
+          break;
+        }
+        match read(region.start + written) {
+          Ok(next) => bytes = next,
+          Err(_) => break,
+        }
+      }
+      if written < length {
+        out.seek(SeekFrom::Start(length_at))?;
+        write!(out, "{:0width$}", written, width = length.to_string().len())?;
+        out.seek(SeekFrom::End(0))?;
+      }
+      saved += 1;
+      total += written;
+    }
+    Ok((saved, total))
+  }
+
+  pub fn read(input: &mut impl BufRead) -> io::Result<Snapshot> {
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+
+    if read_line(input)?.as_deref() != Some(MAGIC) {
+      return Err(invalid("Not a read_memory snapshot"));
+    }
+    let header = read_line(input)?.unwrap_or_default();
+    let (pid, time) = match header.split(' ').collect::<Vec<_>>()[..] {
+      ["pid", pid, "time", time] => (pid.parse().ok(), time.parse().ok()),
+      _ => (None, None),
+    };
+    let (Some(pid), Some(time)) = (pid, time) else {
+      return Err(invalid("Invalid snapshot header"));
+    };
+
+    let mut regions = Vec::new();
+    while let Some(line) = read_line(input)? {
+      // The name comes last as it may contain spaces
+      let fields: Vec<&str> = line.splitn(6, ' ').collect();
+      let ["region", start, end, permissions, length, name] = fields[..] else {
+        return Err(invalid("Invalid region header"));
+      };
+      let (Ok(start), Ok(end), Ok(length)) = (
+        u64::from_str_radix(start, 16),
+        u64::from_str_radix(end, 16),
+        length.parse::<usize>(),
+      ) else {
+        return Err(invalid("Invalid region header"));
+      };
+      // Checked before reading, so a corrupt length cannot ask for a huge buffer
+      if end < start || length as u64 > end - start {
+        return Err(invalid("Region data is longer than the region"));
+      }
+
+      let mut data = Vec::new();
+      input.take(length as u64).read_to_end(&mut data)?;
+      if data.len() != length {
+        return Err(invalid("Snapshot is cut short"));
+      }
+      regions.push(SavedRegion {
+        start,
+        end,
+        permissions: permissions.to_string(),
+        name: name.to_string(),
+        data,
+      });
+    }
+    Ok(Snapshot { pid, time, regions })
+  }
+
+  /// Compare with a later snapshot. Regions are matched by start address;
+  /// differing bytes at most `gap` bytes apart are reported as one change.
+  pub fn diff<'a>(&'a self, new: &'a Snapshot, gap: usize) -> Diff<'a> {
+    let find = |snapshot: &'a Snapshot, start: u64| {
+      snapshot.regions.iter().find(|region| region.start == start)
+    };
+    let mut diff = Diff {
+      changes: Vec::new(),
+      removed: Vec::new(),
+      added: Vec::new(),
+      resized: Vec::new(),
+    };
+
+    for old in &self.regions {
+      let Some(new) = find(new, old.start) else {
+        diff.removed.push(old);
+        continue;
+      };
+      if old.end != new.end || old.data.len() != new.data.len() {
+        diff.resized.push((old, new));
+      }
+      for (offset, length) in changed_ranges(&old.data, &new.data, gap) {
+        diff.changes.push(Change {
+          address: old.start + offset as u64,
+          old: &old.data[offset..offset + length],
+          new: &new.data[offset..offset + length],
+          region: new,
+        });
+      }
+    }
+    diff.added = new
+      .regions
+      .iter()
+      .filter(|region| find(self, region.start).is_none())
+      .collect();
+    diff
+  }
+}
+
+// A line without its newline; None at the end of the input
+fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+  let mut line = String::new();
+  if input.read_line(&mut line)? == 0 {
+    return Ok(None);
+  }
+  Ok(Some(line.trim_end_matches('\n').to_string()))
+}
+
+// (offset, length) of the differing ranges in the common part of two buffers
+fn changed_ranges(old: &[u8], new: &[u8], gap: usize) -> Vec<(usize, usize)> {
+  let mut ranges: Vec<(usize, usize)> = Vec::new();
+  for (offset, _) in old
+    .iter()
+    .zip(new)
+    .enumerate()
+    .filter(|(_, (old, new))| old != new)
+  {
+    match ranges.last_mut() {
+      Some((start, length)) if offset <= *start + *length + gap => {
+        *length = offset + 1 - *start;
+      }
+      _ => ranges.push((offset, 1)),
+    }
+  }
+  ranges
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::memory::Method;
+  use std::io::Cursor;
+  use std::path::Path;
+
+  fn region(start: u64, name: &str, data: &[u8]) -> SavedRegion {
+    SavedRegion {
+      start,
+      end: start + data.len() as u64,
+      permissions: "rw-p".to_string(),
+      name: name.to_string(),
+      data: data.to_vec(),
+    }
+  }
+
+  // A mapping of this process's own memory
+  fn own_region(start: u64, end: u64, name: &str) -> Region {
+    Region::parse(&format!(
+      "{:x}-{:x} rw-p 00000000 00:00 0    {}",
+      start, end, name
+    ))
+    .unwrap()
+  }
+
+  fn save(regions: &[Region]) -> ((usize, u64), Vec<u8>) {
+    let memory =
+      ProcessMemory::open(Path::new("/proc"), std::process::id() as i32, Method::Mem).unwrap();
+    let selected: Vec<&Region> = regions.iter().collect();
+    let mut file = Cursor::new(Vec::new());
+    let saved = Snapshot::save(42, &memory, &selected, &mut file).unwrap();
+    (saved, file.into_inner())
+  }
+
+  #[test]
+  fn round_trips_through_a_file() {
+    let data = b"line\nbreaks\n\x00 and bytes".to_vec();
+    let start = data.as_ptr() as u64;
+    let (saved, file) = save(&[
+      own_region(start, start, "/tmp/name with spaces"),
+      // Never mapped, so left out
+      own_region(0x1000, 0x2000, "[anon]"),
+      own_region(start, start + data.len() as u64, "[heap]"),
+    ]);
+    assert_eq!(saved, (2, data.len() as u64));
+    assert!(file.starts_with(b"read_memory snapshot 1\npid 42 time "));
+    let snapshot = Snapshot::read(&mut file.as_slice()).unwrap();
+    assert_eq!(
+      snapshot.regions,
+      [
+        region(start, "/tmp/name with spaces", b""),
+        region(start, "[heap]", &data)
+      ]
+    );
+
+    assert!(Snapshot::read(&mut &b"something else\n"[..]).is_err());
+    // Cut off in the middle of the data
+    let error = Snapshot::read(&mut &file[..file.len() - 20]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn saves_the_readable_start_of_a_region() {
+    // SAFETY: sysconf has no preconditions
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    // Sixteen pages of which all but the first are unmapped again
+    // SAFETY: a fresh private mapping, only touched through its own pointer
+    let pointer = unsafe {
+      let pointer = libc::mmap(
+        std::ptr::null_mut(),
+        16 * page,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+        -1,
+        0,
+      );
+      assert_ne!(pointer, libc::MAP_FAILED);
+      std::ptr::write_bytes(pointer as *mut u8, b'x', page);
+      libc::munmap(pointer.add(page), 15 * page);
+      pointer
+    };
+    let start = pointer as u64;
+    let (saved, file) = save(&[own_region(start, start + 16 * page as u64, "[anon]")]);
+    // SAFETY: the first page is still mapped and no longer used
+    unsafe { libc::munmap(pointer, page) };
+
+    assert_eq!(saved, (1, page as u64));
+    // The length in the header is padded to the width of the full one
+    let header = format!(
+      "rw-p {:0width$} [anon]\n",
+      page,
+      width = (16 * page).to_string().len()
+    );
+    assert!(String::from_utf8_lossy(&file).contains(&header));
+    let snapshot = Snapshot::read(&mut file.as_slice()).unwrap();
+    assert_eq!(snapshot.regions[0].end, start + 16 * page as u64);
+    assert_eq!(snapshot.regions[0].data, vec![b'x'; page]);
+  }
+
+  #[test]
+  fn rejects_lengths_beyond_the_region() {
+    let header = "read_memory snapshot 1\npid 42 time 0\n";
+    for region in [
+      "region 1000 2000 rw-p 18446744073709551615 [heap]\n",
+      "region 1000 2000 rw-p 4097 [heap]\n",
+      "region 2000 1000 rw-p 0 [heap]\n",
+    ] {
+      let file = format!("{}{}", header, region);
+      let error = Snapshot::read(&mut file.as_bytes()).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", region);
+    }
+
+    let file = format!("{}region 1000 2000 rw-p 4096 [heap]\nshort", header);
+    let error = Snapshot::read(&mut file.as_bytes()).unwrap_err();
+    assert_eq!(error.to_string(), "Snapshot is cut short");
+  }
+
+  #[test]
+  fn reports_changed_ranges_and_regions() {
+    let old = Snapshot {
+      pid: 42,
+      time: 0,
+      regions: vec![
+        region(0x1000, "[heap]", b"aaaaaaaaaaaaaaaa"),
+        region(0x2000, "[anon]", b"gone"),
+      ],
+    };
+    let new = Snapshot {
+      pid: 42,
+      time: 10,
+      regions: vec![
+        region(0x1000, "[heap]", b"aXaXaaaaaaaaaXaaGROWN"),
+        region(0x3000, "[anon]", b"new"),
+      ],
+    };
+
+    let diff = old.diff(&new, 2);
+    let changes: Vec<(u64, &[u8], &[u8])> = diff
+      .changes
+      .iter()
+      .map(|change| (change.address, change.old, change.new))
+      .collect();
+    assert_eq!(
+      changes,
+      [
+        (0x1001, &b"aaa"[..], &b"XaX"[..]),
+        (0x100d, &b"a"[..], &b"X"[..])
+      ]
+    );
+    assert_eq!(diff.removed, [&old.regions[1]]);
+    assert_eq!(diff.added, [&new.regions[1]]);
+    assert_eq!(diff.resized, [(&old.regions[0], &new.regions[0])]);
+
+    assert!(old.diff(&old, 0).changes.is_empty());
+  }
+}
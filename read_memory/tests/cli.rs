@@ -0,0 +1,257 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const HELPER: &str = "READ_MEMORY_HELPER";
+
+/// The helper process for the tests below. Run normally it does nothing; with
+/// READ_MEMORY_HELPER set it puts known values on the heap, prints their
+/// addresses and increments the integer for every line on stdin until it is closed.
+#[test]
+fn helper() {
+  if std::env::var_os(HELPER).is_none() {
+    return;
+  }
+  // Let the sibling read_memory process read us when Yama restricts ptrace
+  // SAFETY: prctl with these options only changes a flag of this process
+  unsafe {
+    libc::prctl(libc::PR_SET_PTRACER, libc::PR_SET_PTRACER_ANY, 0, 0, 0);
+  }
+
+  let text = String::from("helper UTF-8 text");
+  let wide: Vec<u16> = "helper UTF-16 text".encode_utf16().collect();
+  let number = Box::new(0x1bad_cafe_d00d_f00d_u64);
+  let bytes = Box::new(*b"\xde\xad\x42\xef\x99\x88");
+  println!(
+    "ready {:x} {:x} {:x} {:x}",
+    text.as_ptr() as usize,
+    wide.as_ptr() as usize,
+    &*number as *const u64 as usize,
+    bytes.as_ptr() as usize
+  );
+
+  let mut number = number;
+  for _ in std::io::stdin().lines() {
+    *number += 1;
+    println!("bumped");
+  }
+  std::hint::black_box((text, wide, number, bytes));
+}
+
+struct Helper {
+  child: Child,
+  stdin: ChildStdin,
+  stdout: BufReader<ChildStdout>,
+  /// Addresses of the UTF-8 text, UTF-16 text, integer and byte values
+  addresses: Vec<String>,
+}
+
+fn spawn_helper() -> Helper {
+  let mut child = Command::new(std::env::current_exe().unwrap())
+    .args(["helper", "--exact", "--nocapture", "--test-threads=1"])
+    .env(HELPER, "1")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .spawn()
+    .unwrap();
+  let stdin = child.stdin.take().unwrap();
+  let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+  // The test harness prints its own output around ours
+  let mut line = String::new();
+  let ready = loop {
+    line.clear();
+    assert_ne!(stdout.read_line(&mut line).unwrap(), 0, "helper exited");
+    if let Some(index) = line.find("ready ") {
+      break &line[index..];
+    }
+  };
+  let addresses: Vec<String> = ready
+    .split_whitespace()
+    .skip(1)
+    .map(|address| format!("{:0>16}", address))
+    .collect();
+  Helper {
+    child,
+    stdin,
+    stdout,
+    addresses,
+  }
+}
+
+impl Helper {
+  /// Have the helper increment its integer and wait until it did
+  fn bump(&mut self) {
+    writeln!(self.stdin).unwrap();
+    let mut line = String::new();
+    while !line.contains("bumped") {
+      line.clear();
+      assert_ne!(
+        self.stdout.read_line(&mut line).unwrap(),
+        0,
+        "helper exited"
+      );
+    }
+  }
+}
+
+impl Drop for Helper {
+  fn drop(&mut self) {
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
+
+fn read_memory(args: &[&str]) -> String {
+  let output = Command::new(env!("CARGO_BIN_EXE_read_memory"))
+    .args(args)
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn search_finds_every_pattern_kind() {
+  let helper = spawn_helper();
+  let pid = helper.child.id().to_string();
+
+  for (index, args) in [
+    ["-t", "utf8", "helper UTF-8 text"].as_slice(),
+    &["-t", "utf16", "helper UTF-16 text"],
+    &["-t", "int", "-w", "64", "0x1badcafed00df00d"],
+    &["de ad ?? ef ?? 88"],
+  ]
+  .iter()
+  .enumerate()
+  {
+    for method in ["mem", "vm-readv"] {
+      let mut command = vec!["search", pid.as_str(), "-m", method];
+      command.extend_from_slice(args);
+      let output = read_memory(&command);
+      let expected = &helper.addresses[index];
+      assert!(
+        output
+          .lines()
+          .any(|line| line.starts_with(expected.as_str())),
+        "{:?} did not find {}:\n{}",
+        args,
+        expected,
+        output
+      );
+    }
+  }
+}
+
+#[test]
+fn strings_and_dump_show_helper_text() {
+  let helper = spawn_helper();
+  let pid = helper.child.id().to_string();
+
+  // Bytes of the allocator's bookkeeping before it can be printable too
+  let strings = read_memory(&["strings", &pid, "-n", "10"]);
+  assert!(
+    strings
+      .lines()
+      .any(|line| line.ends_with("helper UTF-8 text")),
+    "{}",
+    strings
+  );
+
+  let address = format!("0x{}", helper.addresses[0]);
+  let dump = read_memory(&["dump", &pid, &address, "-n", "16"]);
+  assert!(dump.contains("|helper UTF-8 tex|"), "{}", dump);
+
+  // A length beyond the mapping is cut to it instead of allocated
+  let dump = read_memory(&["dump", &pid, &address, "-n", &usize::MAX.to_string()]);
+  assert!(dump.contains("|helper UTF-8 tex|"), "{}", dump);
+  assert!(dump.lines().count() > 2);
+}
+
+#[test]
+fn diff_finds_the_changed_integer() {
+  let mut helper = spawn_helper();
+  let pid = helper.child.id().to_string();
+  let directory = std::env::temp_dir().join(format!("read_memory_test_{}", pid));
+  std::fs::create_dir_all(&directory).unwrap();
+  let old = directory.join("old");
+  let new = directory.join("new");
+
+  read_memory(&["snapshot", &pid, "-o", old.to_str().unwrap()]);
+  helper.bump();
+  read_memory(&["snapshot", &pid, "-o", new.to_str().unwrap()]);
+  let diff = read_memory(&["diff", old.to_str().unwrap(), new.to_str().unwrap()]);
+  std::fs::remove_dir_all(&directory).unwrap();
+
+  // Printing "bumped" changes other bytes too, but one range must hold the integer
+  let number = u64::from_str_radix(&helper.addresses[2], 16).unwrap();
+  assert!(
+    diff.lines().any(|line| {
+      let Some((address, rest)) = line.split_once("  ") else {
+        return false;
+      };
+      let Some(length) = rest.split_whitespace().nth(1) else {
+        return false;
+      };
+      let (Ok(address), Ok(length)) = (u64::from_str_radix(address, 16), length.parse::<u64>())
+      else {
+        return false;
+      };
+      (address..address + length).contains(&number)
+    }),
+    "{}",
+    diff
+  );
+}
+
+#[test]
+fn maps_groups_mappings_by_file() {
+  let helper = spawn_helper();
+  let pid = helper.child.id().to_string();
+  let executable = std::env::current_exe().unwrap();
+  let executable = executable.to_str().unwrap();
+
+  let maps = read_memory(&["maps", &pid]);
+  assert!(maps.starts_with("Address "));
+  assert!(maps.lines().any(|line| line.ends_with(executable)));
+
+  let grouped = read_memory(&["maps", &pid, "--grouped"]);
+  let lines: Vec<&str> = grouped.lines().collect();
+  assert_eq!(
+    lines
+      .iter()
+      .filter(|line| line.ends_with(executable))
+      .count(),
+    1
+  );
+  assert!(lines.last().unwrap().ends_with("  Total"));
+}
+
+#[test]
+fn maps_from_a_fixture_tree() {
+  let root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc");
+  let maps = read_memory(&["--proc-root", root, "maps", "4242"]);
+  assert!(maps.contains(
+    "00007f2c15e00000  rw-p         132          64          64           8          60  [anon]"
+  ));
+  assert!(maps.contains(
+    "Total                         3096        1184         300           8          92"
+  ));
+
+  let grouped = read_memory(&["maps", "4242", "--grouped", "--proc-root", root]);
+  let lines: Vec<&str> = grouped.lines().collect();
+  assert_eq!(
+    lines[1..],
+    [
+      "      1780        1060         190           0           0     2  /usr/lib/x86_64-linux-gnu/libc.so.6",
+      "      1024          64          64           8          60     2  [anon]",
+      "       132          20          20           0          20     1  [stack]",
+      "        28          28          14           0           0     2  /usr/bin/cat",
+      "       132          12          12           0          12     1  [heap]",
+      "      3096        1184         300           8          92     8  Total",
+    ]
+  );
+}